@@ -0,0 +1,71 @@
+use interleague_schedule::interleague_schedule::*;
+use interleague_schedule::sample_data::*;
+use interleague_schedule::Config;
+use std::time::{Duration, Instant};
+
+// Times the backtracking search on the 2023 AAA calendar, with every team's target set to fill the
+// days as published. Configs are walked in the order backtrack::solve would, in two modes:
+//
+// - solve: stops at the first solution, or after NODES configs
+// - fixed: walks NODES configs whatever goals it passes, or the whole tree if smaller, so the time
+//   per config can be compared across changes to the search that find solutions sooner or later
+//
+// cargo bench --bench schedule
+const NODES: usize = 200_000;
+
+// Depth first, like backtrack::solve, stopping after max_nodes configs and, if stop_at_goal, at
+// the first solution. Returns the configs visited and whether a solution was found.
+fn walk(config: ScheduleConfig, max_nodes: usize, stop_at_goal: bool) -> (usize, bool) {
+    let mut stack = vec![config];
+    let mut nodes = 0;
+    let mut solved = false;
+    while let Some(config) = stack.pop() {
+        nodes += 1;
+        if config.is_goal() {
+            solved = true;
+            if stop_at_goal {
+                break;
+            }
+        }
+        if nodes == max_nodes {
+            break;
+        }
+        let mut successors: Vec<ScheduleConfig> = config
+            .successors()
+            .into_iter()
+            .filter(|s| s.is_valid())
+            .collect();
+        successors.reverse();
+        stack.extend(successors);
+    }
+    (nodes, solved)
+}
+
+fn time_walk(config: &ScheduleConfig, mode: &str, stop_at_goal: bool) {
+    let mut runs = 0;
+    let mut nodes = 0;
+    let mut solved = false;
+    let mut total = Duration::ZERO;
+    while runs < 3 || total < Duration::from_secs(2) {
+        let start = Instant::now();
+        let (run_nodes, run_solved) = walk(config.clone(), NODES, stop_at_goal);
+        total += start.elapsed();
+        nodes += run_nodes;
+        solved = run_solved;
+        runs += 1;
+    }
+    println!(
+        "2023 AAA {}: {:?} per run of {} configs ({}), {:.2} us per config",
+        mode,
+        total / runs,
+        nodes / runs as usize,
+        if solved { "solved" } else { "unsolved" },
+        total.as_secs_f64() * 1e6 / nodes as f64
+    );
+}
+
+fn main() {
+    let (_, config) = get_2023_aaa_filled_config().unwrap();
+    time_walk(&config, "solve", true);
+    time_walk(&config, "fixed", false);
+}
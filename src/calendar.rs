@@ -0,0 +1,206 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ScheduleError;
+use crate::interleague_schedule::*;
+use crate::team_set::TeamSet;
+use crate::teams::*;
+
+// Season days generated from rules instead of listed one by one: every listed weekday from start
+// to end, skipping holidays, with teams left out of the days they've blacked out.
+//
+// start = "2024-03-23"
+// end = "2024-05-21"
+// weekdays = ["Tue", "Sat"]
+// holidays = [{ name = "Spring break", first = "2024-04-03", last = "2024-04-15" }]
+// blackouts = [{ team = "NE 2", first = "2024-05-04", last = "2024-05-04" }]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CalendarRules {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub weekdays: Vec<Weekday>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub holidays: Vec<Holiday>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blackouts: Vec<Blackout>,
+}
+
+// No games from first to last, inclusive.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Holiday {
+    pub name: String,
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+}
+
+// A team (or a whole league, by its name) that can't play from first to last, inclusive.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Blackout {
+    pub team: TeamRef,
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+}
+
+impl CalendarRules {
+    pub fn validate(&self, state: &State) -> Result<(), ScheduleError> {
+        if self.start > self.end {
+            return Err(ScheduleError::BadDateRange {
+                first: self.start,
+                last: self.end,
+            });
+        }
+        for holiday in self.holidays.iter() {
+            if holiday.first > holiday.last {
+                return Err(ScheduleError::BadDateRange {
+                    first: holiday.first,
+                    last: holiday.last,
+                });
+            }
+        }
+        for blackout in self.blackouts.iter() {
+            if blackout.first > blackout.last {
+                return Err(ScheduleError::BadDateRange {
+                    first: blackout.first,
+                    last: blackout.last,
+                });
+            }
+            if state.resolve(&blackout.team).is_none() {
+                return Err(ScheduleError::UnknownTeam {
+                    date: blackout.first,
+                    team: blackout.team.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // Dates from start to end on one of the weekdays and not in a holiday.
+    pub fn dates(&self) -> Vec<NaiveDate> {
+        self.start
+            .iter_days()
+            .take_while(|date| *date <= self.end)
+            .filter(|date| self.weekdays.contains(&date.weekday()))
+            .filter(|date| {
+                !self
+                    .holidays
+                    .iter()
+                    .any(|h| h.first <= *date && *date <= h.last)
+            })
+            .collect()
+    }
+
+    // Teams blacked out on a date.
+    pub fn unavailable(&self, date: NaiveDate) -> Vec<TeamRef> {
+        let mut teams: Vec<TeamRef> = Vec::new();
+        for blackout in self.blackouts.iter() {
+            if blackout.first <= date && date <= blackout.last && !teams.contains(&blackout.team) {
+                teams.push(blackout.team.clone());
+            }
+        }
+        teams
+    }
+}
+
+// Days for ScheduleConfig::new.
+pub fn get_days(rules: &CalendarRules, state: &State) -> Result<Vec<Day>, ScheduleError> {
+    if state.teams.len() > TeamSet::MAX_TEAMS {
+        return Err(ScheduleError::TooManyTeams(state.teams.len()));
+    }
+    rules.validate(state)?;
+    let days: Vec<Day> = rules
+        .dates()
+        .into_iter()
+        .map(|date| {
+            let unavailable = rules.unavailable(date);
+            let teams_playing = state.teams_except(unavailable.iter()).unwrap_or_default();
+            Day::new(date, teams_playing)
+        })
+        .collect();
+    if days.is_empty() {
+        return Err(ScheduleError::NoDays);
+    }
+    if let Some(day) = days
+        .iter()
+        .find(|d| !d.teams_playing.len().is_multiple_of(2))
+    {
+        return Err(ScheduleError::OddTeamsPlaying {
+            date: day.date,
+            count: day.teams_playing.len(),
+        });
+    }
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calendar::*;
+    use crate::sample_data::get_state_from_league_info;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    #[test]
+    fn generate() {
+        let mut rules = CalendarRules {
+            start: date(3, 23),
+            end: date(5, 21),
+            weekdays: vec![Weekday::Tue, Weekday::Sat],
+            holidays: vec![Holiday {
+                name: "Spring break".to_string(),
+                first: date(4, 3),
+                last: date(4, 15),
+            }],
+            blackouts: vec![Blackout {
+                team: TeamRef::Name("A 2".to_string()),
+                first: date(5, 1),
+                last: date(5, 7),
+            }],
+        };
+        let dates: Vec<String> = rules
+            .dates()
+            .iter()
+            .map(|d| d.format("%-m/%d").to_string())
+            .collect();
+        assert_eq!(
+            dates,
+            vec![
+                "3/23", "3/26", "3/30", "4/02", "4/16", "4/20", "4/23", "4/27", "4/30", "5/04",
+                "5/07", "5/11", "5/14", "5/18", "5/21"
+            ]
+        );
+
+        let state = get_state_from_league_info(&[("A".to_string(), 4)], 1);
+        assert!(matches!(
+            get_days(&rules, &state),
+            Err(ScheduleError::OddTeamsPlaying { count: 3, .. })
+        ));
+        rules.blackouts.push(Blackout {
+            team: TeamRef::Index(2),
+            first: date(5, 4),
+            last: date(5, 7),
+        });
+        let days = get_days(&rules, &state).unwrap();
+        assert_eq!(days.len(), 15);
+        assert_eq!(
+            days[9].teams_playing,
+            [TeamId(0), TeamId(3)].into_iter().collect()
+        );
+        assert_eq!(days[11].teams_playing.len(), 4);
+
+        rules.end = date(3, 1);
+        assert!(matches!(
+            get_days(&rules, &state),
+            Err(ScheduleError::BadDateRange { .. })
+        ));
+
+        let state = get_state_from_league_info(&[("A".to_string(), 130)], 1);
+        assert!(matches!(
+            get_days(&rules, &state),
+            Err(ScheduleError::TooManyTeams(130))
+        ));
+    }
+}
@@ -0,0 +1,255 @@
+use chrono::NaiveDate;
+use std::fmt::Write;
+
+use crate::interleague_schedule::*;
+use crate::team_set::TeamSet;
+use crate::teams::*;
+
+// Why a ScheduleConfig can't be solved. Every day has to be filled, so each team needs as many
+// games as it has available days (or no more, for teams that can sit days out to rest), every
+// team playing on a date needs an opponent that also plays that date, and the date needs fields
+// for all of their games.
+pub struct Diagnosis {
+    // (team, games, available days) for teams whose games don't fit their available days.
+    pub teams: Vec<(TeamId, usize, usize)>,
+    pub dates: Vec<DateProblem>,
+    // Games whose teams never share an available day.
+    pub unplaceable_games: Vec<Game>,
+}
+
+pub struct DateProblem {
+    pub date: NaiveDate,
+    pub teams_playing: usize,
+    // Teams playing that date without a remaining game against another team playing that date.
+    pub teams_without_opponent: Vec<TeamId>,
+    // Games the host leagues have fields for, if fewer than the teams playing need.
+    pub fields: Option<usize>,
+}
+
+impl Diagnosis {
+    pub fn is_empty(&self) -> bool {
+        self.teams.is_empty() && self.dates.is_empty() && self.unplaceable_games.is_empty()
+    }
+
+    pub fn format(&self, state: &State) -> String {
+        let mut s = String::new();
+        if self.is_empty() {
+            s.push_str("No single team, date or game is infeasible on its own.\n");
+            return s;
+        }
+        for (ti, games, days) in self.teams.iter() {
+            let problem = if games > days {
+                "not enough days"
+            } else {
+                "days left without a game"
+            };
+            let _ = writeln!(
+                s,
+                "{}: {} games but {} available days ({})",
+                state.team_name(*ti),
+                games,
+                days,
+                problem
+            );
+        }
+        for d in self.dates.iter() {
+            if d.teams_playing % 2 != 0 {
+                let _ = writeln!(
+                    s,
+                    "{}: odd number of teams playing ({})",
+                    d.date, d.teams_playing
+                );
+            }
+            if !d.teams_without_opponent.is_empty() {
+                let names: Vec<&str> = d
+                    .teams_without_opponent
+                    .iter()
+                    .map(|id| state.team_name(*id))
+                    .collect();
+                let _ = writeln!(s, "{}: no opponent for {}", d.date, names.join(", "));
+            }
+            if let Some(fields) = d.fields {
+                let _ = writeln!(
+                    s,
+                    "{}: {} teams playing but fields for {} games",
+                    d.date, d.teams_playing, fields
+                );
+            }
+        }
+        for g in self.unplaceable_games.iter() {
+            let _ = writeln!(
+                s,
+                "{} - {}: no date both teams play",
+                state.team_name(g.ti0),
+                state.team_name(g.ti1)
+            );
+        }
+        s
+    }
+}
+
+pub fn diagnose_schedule(config: &ScheduleConfig, state: &State) -> Diagnosis {
+    diagnose_days(
+        &config.days()[config.days_index()..],
+        &config.remaining_games(),
+        state,
+    )
+}
+
+// Same as diagnose_schedule, for days and games that don't make a valid ScheduleConfig.
+pub fn diagnose_days(days: &[Day], remaining_games: &[Game], state: &State) -> Diagnosis {
+    let num_teams = state.teams.len();
+    let mut games = vec![0; num_teams];
+    let mut opponents: Vec<TeamSet> = vec![TeamSet::new(); num_teams];
+    for g in remaining_games.iter() {
+        games[g.ti0.0] += 1;
+        games[g.ti1.0] += 1;
+        opponents[g.ti0.0].insert(g.ti1);
+        opponents[g.ti1.0].insert(g.ti0);
+    }
+    let mut available_days = vec![0; num_teams];
+    for d in days.iter() {
+        for id in d.teams_playing.iter() {
+            available_days[id.0] += 1;
+        }
+    }
+    let teams = (0..num_teams)
+        .filter(|ti| {
+            games[*ti] > available_days[*ti]
+                || (games[*ti] < available_days[*ti] && !state.rest.can_rest(TeamId(*ti)))
+        })
+        .map(|ti| (TeamId(ti), games[ti], available_days[ti]))
+        .collect();
+
+    let mut dates = Vec::new();
+    for d in days.iter() {
+        let mut teams_without_opponent: Vec<TeamId> = d
+            .teams_playing
+            .iter()
+            .filter(|id| opponents[id.0].is_disjoint(&d.teams_playing))
+            .collect();
+        teams_without_opponent.sort();
+        let fields = get_field_capacity(d, state).filter(|f| *f < d.teams_playing.len() / 2);
+        if d.teams_playing.len() % 2 != 0 || !teams_without_opponent.is_empty() || fields.is_some()
+        {
+            dates.push(DateProblem {
+                date: d.date,
+                teams_playing: d.teams_playing.len(),
+                teams_without_opponent,
+                fields,
+            });
+        }
+    }
+
+    let mut unplaceable_games: Vec<Game> = Vec::new();
+    for g in remaining_games.iter() {
+        let placeable = days
+            .iter()
+            .any(|d| d.teams_playing.contains(g.ti0) && d.teams_playing.contains(g.ti1));
+        let listed = unplaceable_games
+            .iter()
+            .any(|u| u.ti0 == g.ti0 && u.ti1 == g.ti1);
+        if !placeable && !listed {
+            unplaceable_games.push(*g);
+        }
+    }
+
+    Diagnosis {
+        teams,
+        dates,
+        unplaceable_games,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnose::*;
+    use crate::sample_data::get_state_from_league_info;
+
+    #[test]
+    fn diagnose() {
+        let mut state = get_state_from_league_info(&[("A".to_string(), 4)], 2);
+        let teams = |teams: Vec<usize>| teams.into_iter().map(TeamId).collect();
+        let day = |day: u32, playing: Vec<usize>| {
+            let date = NaiveDate::from_ymd_opt(2023, 3, day).unwrap();
+            Day::new(date, teams(playing))
+        };
+        let game = |ti0, ti1| Game {
+            ti0: TeamId(ti0),
+            ti1: TeamId(ti1),
+            distance: 0,
+            home: None,
+        };
+        let games = vec![game(0, 1), game(0, 1), game(2, 3), game(0, 3)];
+        let days = vec![day(23, vec![0, 1]), day(27, vec![1, 2, 3])];
+
+        let diagnosis = diagnose_days(&days, &games, &state);
+        assert_eq!(diagnosis.teams, vec![(TeamId(0), 3, 1), (TeamId(3), 2, 1)]);
+        let text = diagnosis.format(&state);
+        assert!(text.contains("A 1: 3 games but 1 available days (not enough days)"));
+        assert!(text.contains("2023-03-27: odd number of teams playing (3)"));
+        assert!(text.contains("2023-03-27: no opponent for A 2"));
+        assert!(text.contains("A 1 - A 4: no date both teams play"));
+        assert!(!text.contains("2023-03-23"));
+
+        // The same games over days that ScheduleConfig::new accepts.
+        let days = vec![day(23, vec![0, 1, 2, 3]), day(27, vec![0, 1, 2, 3])];
+        let config = ScheduleConfig::new(days, games.clone(), &state).unwrap();
+        let diagnosis = diagnose_schedule(&config, &state);
+        assert_eq!(diagnosis.teams, vec![(TeamId(0), 3, 2), (TeamId(2), 1, 2)]);
+        assert!(diagnosis.dates.is_empty());
+
+        // A team that can rest may have spare days, and A can only host one game a day.
+        state.rest.groups.push(RestGroup {
+            teams: vec![TeamId(2), TeamId(3)],
+            min_days: 0,
+        });
+        state.leagues[0].venues.push(Venue {
+            name: "Park".to_string(),
+            fields: 1,
+            fields_on: Default::default(),
+        });
+        let days = vec![day(23, vec![0, 1, 2, 3]), day(27, vec![0, 1, 2, 3])];
+        let diagnosis = diagnose_days(&days, &games, &state);
+        assert_eq!(diagnosis.teams, vec![(TeamId(0), 3, 2)]);
+        let text = diagnosis.format(&state);
+        assert!(text.contains("2023-03-23: 4 teams playing but fields for 1 games"));
+    }
+
+    #[test]
+    fn spare_days() {
+        // Two games each over three Saturdays.
+        let mut state =
+            get_state_from_league_info(&[("A".to_string(), 2), ("B".to_string(), 2)], 2);
+        let days: Vec<Day> = [(3, 23), (3, 30), (4, 6)]
+            .iter()
+            .map(|(month, day)| {
+                let date = NaiveDate::from_ymd_opt(2024, *month, *day).unwrap();
+                Day::new(date, (0..4).map(TeamId).collect())
+            })
+            .collect();
+        let games: Vec<Game> = [(0, 2), (0, 3), (1, 2), (1, 3)]
+            .iter()
+            .map(|(ti0, ti1)| Game {
+                ti0: TeamId(*ti0),
+                ti1: TeamId(*ti1),
+                distance: 0,
+                home: None,
+            })
+            .collect();
+
+        // Without rest rules no team can sit a day out.
+        let diagnosis = diagnose_days(&days, &games, &state);
+        let spare: Vec<(TeamId, usize, usize)> = (0..4).map(|ti| (TeamId(ti), 2, 3)).collect();
+        assert_eq!(diagnosis.teams, spare);
+        assert!(diagnosis
+            .format(&state)
+            .contains("B 1: 2 games but 3 available days (days left without a game)"));
+
+        // With them the search uses the spare days, so nothing is reported.
+        state.rest.min_days = 1;
+        assert!(diagnose_days(&days, &games, &state).is_empty());
+        let config = ScheduleConfig::new(days, games, &state).unwrap();
+        assert!(matches!(solve_schedule(config), ScheduleOutcome::Solved(_)));
+    }
+}
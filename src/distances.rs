@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+
+use crate::error::ScheduleError;
+
+// League distances worked out from where leagues play, rather than typed in a pair at a time.
+// Either each league's home field location or a CSV of drive times gives a raw distance in
+// kilometers or minutes, which is then used as is (rounded) or bucketed into tiers.
+//
+// Drive time CSVs have a header row, then a row per pair of leagues in either order:
+//
+// From,To,Minutes
+// SL,NE,18
+// SL,MAG,25
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+// Great circle distance in kilometers.
+pub fn get_distance_km(a: &Location, b: &Location) -> f64 {
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+    let (lat0, lat1) = (a.latitude.to_radians(), b.latitude.to_radians());
+    let dlat = lat1 - lat0;
+    let dlon = (b.longitude - a.longitude).to_radians();
+    let h = (dlat / 2.0).sin().powi(2) + lat0.cos() * lat1.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+// Tiers are the upper bounds of each distance score from 1 up, so [5, 10] scores 4 km as 1,
+// 7 km as 2 and anything past 10 km as 3.
+pub fn validate_tiers(tiers: &[f64]) -> Result<(), ScheduleError> {
+    let increasing = tiers.windows(2).all(|w| w[0] < w[1]);
+    if tiers.is_empty() || tiers[0] <= 0.0 || !increasing {
+        return Err(ScheduleError::BadTiers(tiers.to_vec()));
+    }
+    Ok(())
+}
+
+// Distance score for a raw distance: rounded without tiers, otherwise its tier.
+pub fn get_distance_score(distance: f64, tiers: Option<&[f64]>) -> i32 {
+    match tiers {
+        None => distance.round() as i32,
+        Some(tiers) => 1 + tiers.iter().filter(|bound| distance > **bound).count() as i32,
+    }
+}
+
+// Drive times keyed by league index pair, smallest first.
+pub fn read_drive_times<R: io::Read>(
+    reader: R,
+    league_names: &[String],
+) -> Result<HashMap<(usize, usize), f64>, ScheduleError> {
+    let league_index = |name: &str| {
+        league_names
+            .iter()
+            .position(|l| l == name)
+            .ok_or_else(|| ScheduleError::UnknownLeague(name.to_string()))
+    };
+    let mut reader = csv::Reader::from_reader(reader);
+    let mut drive_times = HashMap::new();
+    for (row, record) in reader.records().enumerate() {
+        let record = record?;
+        let field = |i: usize| record.get(i).unwrap_or("").trim();
+        let (name0, name1) = (field(0), field(1));
+        let (li0, li1) = (league_index(name0)?, league_index(name1)?);
+        let minutes: f64 = field(2).parse().map_err(|_| ScheduleError::BadDriveTime {
+            row: row + 2,
+            value: field(2).to_string(),
+        })?;
+        if minutes < 0.0 {
+            return Err(ScheduleError::NegativeDistance(
+                name0.to_string(),
+                name1.to_string(),
+            ));
+        }
+        if drive_times
+            .insert((li0.min(li1), li0.max(li1)), minutes)
+            .is_some()
+        {
+            return Err(ScheduleError::DuplicateDistance(
+                name0.to_string(),
+                name1.to_string(),
+            ));
+        }
+    }
+    Ok(drive_times)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::distances::*;
+
+    #[test]
+    fn scores() {
+        let a = Location {
+            latitude: 47.0,
+            longitude: -122.0,
+        };
+        let b = Location {
+            latitude: 48.0,
+            longitude: -122.0,
+        };
+        assert_eq!(get_distance_km(&a, &b).round(), 111.0);
+        assert_eq!(get_distance_score(12.4, None), 12);
+
+        let tiers = [5.0, 10.0];
+        assert!(validate_tiers(&tiers).is_ok());
+        assert!(validate_tiers(&[10.0, 5.0]).is_err());
+        let scores: Vec<i32> = [4.0, 5.0, 7.0, 11.0]
+            .iter()
+            .map(|d| get_distance_score(*d, Some(&tiers)))
+            .collect();
+        assert_eq!(scores, vec![1, 1, 2, 3]);
+
+        let names = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let csv = "From,To,Minutes\nB,A,18\nA,C,25.5\n";
+        let drive_times = read_drive_times(csv.as_bytes(), &names).unwrap();
+        assert_eq!(drive_times[&(0, 1)], 18.0);
+        assert_eq!(drive_times[&(0, 2)], 25.5);
+        assert!(matches!(
+            read_drive_times("From,To,Minutes\nA,B,x\n".as_bytes(), &names),
+            Err(ScheduleError::BadDriveTime { row: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::teams::*;
+
+// Spreading travel fairly instead of (or as well as) keeping total travel low. Starting from
+// matchups from either stage, interleague games are swapped in pairs (a - b and c - d become
+// a - c and b - d, or a - d and b - c), which keeps every team's game count, as long as the swap
+// improves the objective.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TravelObjective {
+    // Lowest total travel score, what the matchup stages already aim for.
+    Total,
+    // Lowest highest team travel score, then the next highest, and so on.
+    MaxTeam,
+    // Same as MaxTeam, with each league's average team travel score.
+    MaxLeague,
+    // Lowest variance of team travel scores.
+    Variance,
+}
+
+// Sort key for an objective, lowest is best. Ties go to lower total travel.
+fn get_key(objective: TravelObjective, state: &State, travel_scores: &[i32]) -> Vec<i64> {
+    let total: i64 = travel_scores.iter().map(|t| *t as i64).sum();
+    let mut key = match objective {
+        TravelObjective::Total => vec![],
+        TravelObjective::MaxTeam => travel_scores.iter().map(|t| *t as i64).collect(),
+        TravelObjective::MaxLeague => get_league_travel_scores(state, travel_scores)
+            .iter()
+            .map(|t| (t * 1000.0).round() as i64)
+            .collect(),
+        TravelObjective::Variance => {
+            let sum_squares: i64 = travel_scores.iter().map(|t| (*t as i64).pow(2)).sum();
+            // Variance times the number of teams squared.
+            vec![travel_scores.len() as i64 * sum_squares - total * total]
+        }
+    };
+    if matches!(
+        objective,
+        TravelObjective::MaxTeam | TravelObjective::MaxLeague
+    ) {
+        key.sort_by(|a, b| b.cmp(a));
+    }
+    key.push(total);
+    key
+}
+
+// Average team travel score per league.
+pub fn get_league_travel_scores(state: &State, travel_scores: &[i32]) -> Vec<f64> {
+    state
+        .leagues
+        .iter()
+        .map(|league| {
+            let (first, last) = league.team_index_range;
+            let total: i32 = travel_scores[first..last].iter().sum();
+            total as f64 / (last - first) as f64
+        })
+        .collect()
+}
+
+// Swaps interleague games until no swap improves the objective. New pairs can't be in-league or
+// meet more often than any interleague pair already does.
+pub fn balance_travel(
+    state: &mut State,
+    teams_matrix: &mut Matrix,
+    league_distance_matrix: &Matrix,
+    objective: TravelObjective,
+) {
+    let league = |state: &State, ti: usize| state.teams[ti].league;
+    let mut games: Vec<(usize, usize)> = Vec::new();
+    let mut max_meetings = 0;
+    for ti0 in 0..state.teams.len() {
+        for ti1 in ti0 + 1..state.teams.len() {
+            if league(state, ti0) != league(state, ti1) {
+                let meetings = get_matrix_val(teams_matrix, ti0, ti1);
+                for _ in 0..meetings {
+                    games.push((ti0, ti1));
+                }
+                max_meetings = max_meetings.max(meetings);
+            }
+        }
+    }
+
+    let distance = |state: &State, ti0: usize, ti1: usize| {
+        get_matrix_val(
+            league_distance_matrix,
+            league(state, ti0).0,
+            league(state, ti1).0,
+        )
+    };
+    let mut travel_scores = get_travel_scores(state, league_distance_matrix);
+    let mut key = get_key(objective, state, &travel_scores);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for gi in 0..games.len() {
+            for gj in gi + 1..games.len() {
+                let (a, b) = games[gi];
+                let (c, d) = games[gj];
+                for (new0, new1) in [((a, c), (b, d)), ((a, d), (b, c))] {
+                    let (new0, new1) = (
+                        (new0.0.min(new0.1), new0.0.max(new0.1)),
+                        (new1.0.min(new1.1), new1.0.max(new1.1)),
+                    );
+                    let meetings = |(x, y): (usize, usize)| {
+                        get_matrix_val(teams_matrix, x, y) + if new0 == new1 { 2 } else { 1 }
+                    };
+                    let valid = |pair: (usize, usize)| {
+                        league(state, pair.0) != league(state, pair.1)
+                            && meetings(pair) <= max_meetings
+                    };
+                    if !valid(new0) || !valid(new1) {
+                        continue;
+                    }
+                    let mut new_travel_scores = travel_scores.clone();
+                    for (x, y, sign) in [
+                        (a, b, -1),
+                        (c, d, -1),
+                        (new0.0, new0.1, 1),
+                        (new1.0, new1.1, 1),
+                    ] {
+                        new_travel_scores[x] += sign * distance(state, x, y);
+                        new_travel_scores[y] += sign * distance(state, x, y);
+                    }
+                    let new_key = get_key(objective, state, &new_travel_scores);
+                    if new_key < key {
+                        remove_game(&mut state.teams, teams_matrix, a, b);
+                        remove_game(&mut state.teams, teams_matrix, c, d);
+                        add_game(&mut state.teams, teams_matrix, new0.0, new0.1);
+                        add_game(&mut state.teams, teams_matrix, new1.0, new1.1);
+                        games[gi] = new0;
+                        games[gj] = new1;
+                        travel_scores = new_travel_scores;
+                        key = new_key;
+                        improved = true;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::equity::*;
+    use crate::sample_data::*;
+
+    // Four one-team leagues a, b, c and d playing two games each, starting from a - b, b - d,
+    // d - c, c - a. The only other ways to pair them up are a - b, b - c, c - d, d - a and a - c,
+    // c - b, b - d, d - a. Distances are a - b, a - c, a - d, b - c, b - d, c - d. Returns the
+    // pairs that meet.
+    fn balance_four(distances: [i32; 6], objective: TravelObjective) -> Vec<(usize, usize)> {
+        let league_info: Vec<(String, usize)> = ["A", "B", "C", "D"]
+            .iter()
+            .map(|l| (l.to_string(), 1))
+            .collect();
+        let mut state = get_state_from_league_info(&league_info, 2);
+        let mut league_distance_matrix = create_matrix_with_dimension(4);
+        let pairs = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+        for ((li0, li1), distance) in pairs.iter().zip(distances) {
+            league_distance_matrix[*li0][*li1] = distance;
+        }
+        let mut matrix = create_matrix_with_dimension(4);
+        for (ti0, ti1) in [(0, 1), (1, 3), (2, 3), (0, 2)] {
+            add_game(&mut state.teams, &mut matrix, ti0, ti1);
+        }
+        balance_travel(&mut state, &mut matrix, &league_distance_matrix, objective);
+        assert!(state.teams.iter().all(|t| t.num_games == 2));
+        pairs
+            .into_iter()
+            .filter(|(ti0, ti1)| matrix[*ti0][*ti1] > 0)
+            .collect()
+    }
+
+    // Travel scores a 2, b 8, c 2, d 8 to start with, total 20. The cycle through a - d and
+    // b - c gives everyone 6, total 24.
+    const EVEN: [i32; 6] = [1, 1, 5, 5, 7, 1];
+
+    // Travel scores a 2, b 7, c 2, d 7 to start with. The cycle through a - d and b - c gives a 4,
+    // b 8, c 8, d 4: a higher maximum but a lower variance.
+    const SPREAD: [i32; 6] = [1, 1, 3, 7, 6, 1];
+
+    const START: [(usize, usize); 4] = [(0, 1), (0, 2), (1, 3), (2, 3)];
+    const EVEN_CYCLE: [(usize, usize); 4] = [(0, 1), (0, 3), (1, 2), (2, 3)];
+
+    #[test]
+    fn total() {
+        assert_eq!(balance_four(EVEN, TravelObjective::Total), START);
+    }
+
+    #[test]
+    fn max_team() {
+        assert_eq!(balance_four(EVEN, TravelObjective::MaxTeam), EVEN_CYCLE);
+        assert_eq!(balance_four(SPREAD, TravelObjective::MaxTeam), START);
+    }
+
+    #[test]
+    fn max_league() {
+        assert_eq!(balance_four(EVEN, TravelObjective::MaxLeague), EVEN_CYCLE);
+        assert_eq!(balance_four(SPREAD, TravelObjective::MaxLeague), START);
+    }
+
+    #[test]
+    fn variance() {
+        assert_eq!(balance_four(EVEN, TravelObjective::Variance), EVEN_CYCLE);
+        assert_eq!(balance_four(SPREAD, TravelObjective::Variance), EVEN_CYCLE);
+    }
+
+    #[test]
+    fn keeps_game_counts() {
+        let league_distance_matrix = get_district8_city_league_distances();
+        let mut state = get_2023_aaa_softball_state();
+        state.set_target_games(14);
+        let mut matrix = get_teams_to_play_against(&mut state, &league_distance_matrix).unwrap();
+        balance_travel(
+            &mut state,
+            &mut matrix,
+            &league_distance_matrix,
+            TravelObjective::MaxTeam,
+        );
+        assert!(state.teams.iter().all(|t| t.num_games == 14));
+        let total: i32 = matrix.iter().flatten().sum();
+        assert_eq!(total, 14 * 23 / 2);
+    }
+}
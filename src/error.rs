@@ -0,0 +1,249 @@
+use chrono::NaiveDate;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::team_set::TeamSet;
+
+#[derive(Debug)]
+pub enum ScheduleError {
+    Io(io::Error),
+    Csv(csv::Error),
+    Parse(String),
+    Serialize(String),
+
+    // Season files.
+    NoLeagues,
+    TooManyTeams(usize),
+    EmptyLeague(String),
+    DuplicateLeague(String),
+    UnknownLeague(String),
+    DuplicateDistance(String, String),
+    MissingDistance(String, String),
+    NegativeDistance(String, String),
+    BadTiers(Vec<f64>),
+    MissingDriveTime(String, String),
+    // Distances worked out without tiers, alongside listed ones.
+    UntieredDistances,
+    BadLocation(String),
+    BadDriveTime {
+        row: usize,
+        value: String,
+    },
+    NonPositiveMaxGames(i32),
+    NoDays,
+    UnknownTeam {
+        date: NaiveDate,
+        team: String,
+    },
+    // date is None for a game's team.
+    UnknownTeamIndex {
+        date: Option<NaiveDate>,
+        index: usize,
+    },
+    UnknownVenueDate {
+        venue: String,
+        date: NaiveDate,
+    },
+    UnknownRestTeam(String),
+    UnknownTargetTeam(String),
+
+    // Schedule grids.
+    EmptyGrid,
+    UnknownTeamName {
+        row: usize,
+        name: String,
+    },
+    NoAwayTeam {
+        row: usize,
+        column: usize,
+    },
+
+    // Teams that can't reach their target games, and by how many games.
+    GamesShort(Vec<(String, i32)>),
+
+    // Days.
+    BadDate(String),
+    BadDateRange {
+        first: NaiveDate,
+        last: NaiveDate,
+    },
+    DuplicateDate(NaiveDate),
+    OddTeamsPlaying {
+        date: NaiveDate,
+        count: usize,
+    },
+    DaysForTarget {
+        team: String,
+        days: usize,
+        target_games: i32,
+    },
+    NotEnoughFields {
+        date: NaiveDate,
+        games: usize,
+        fields: usize,
+        leagues: Vec<String>,
+    },
+    // Games placed on a day that the search wouldn't have scheduled there.
+    BrokenRules(NaiveDate),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::Io(e) => write!(formatter, "{}", e),
+            ScheduleError::Csv(e) => write!(formatter, "CSV: {}", e),
+            ScheduleError::Parse(e) => write!(formatter, "can't parse season file: {}", e),
+            ScheduleError::Serialize(e) => write!(formatter, "can't write season file: {}", e),
+            ScheduleError::NoLeagues => write!(formatter, "season has no leagues"),
+            ScheduleError::TooManyTeams(count) => write!(
+                formatter,
+                "season has {} teams, at most {} are supported",
+                count,
+                TeamSet::MAX_TEAMS
+            ),
+            ScheduleError::EmptyLeague(name) => write!(formatter, "league {} has no teams", name),
+            ScheduleError::DuplicateLeague(name) => {
+                write!(formatter, "league {} is listed more than once", name)
+            }
+            ScheduleError::UnknownLeague(name) => write!(formatter, "unknown league {}", name),
+            ScheduleError::DuplicateDistance(a, b) => {
+                write!(formatter, "distance {} - {} is listed more than once", a, b)
+            }
+            ScheduleError::MissingDistance(a, b) => {
+                write!(formatter, "missing distance {} - {}", a, b)
+            }
+            ScheduleError::NegativeDistance(a, b) => {
+                write!(formatter, "distance {} - {} is negative", a, b)
+            }
+            ScheduleError::BadTiers(tiers) => write!(
+                formatter,
+                "distance tiers {:?} need to be positive and increasing",
+                tiers
+            ),
+            ScheduleError::MissingDriveTime(a, b) => {
+                write!(formatter, "drive times are missing {} - {}", a, b)
+            }
+            ScheduleError::UntieredDistances => write!(
+                formatter,
+                "distances worked out from drive times or locations need distance_tiers to be \
+                 mixed with listed distances"
+            ),
+            ScheduleError::BadLocation(league) => write!(
+                formatter,
+                "league {} location needs a latitude from -90 to 90 and a longitude from -180 to \
+                 180",
+                league
+            ),
+            ScheduleError::BadDriveTime { row, value } => {
+                write!(
+                    formatter,
+                    "drive times row {}: bad minutes \"{}\"",
+                    row, value
+                )
+            }
+            ScheduleError::NonPositiveMaxGames(max_games) => {
+                write!(formatter, "max_games must be positive, got {}", max_games)
+            }
+            ScheduleError::NoDays => write!(formatter, "season has no days"),
+            ScheduleError::UnknownTeam { date, team } => {
+                write!(
+                    formatter,
+                    "{}: unavailable team {} doesn't exist",
+                    date, team
+                )
+            }
+            ScheduleError::UnknownTeamIndex {
+                date: Some(date),
+                index,
+            } => write!(formatter, "{}: team index {} doesn't exist", date, index),
+            ScheduleError::UnknownTeamIndex { date: None, index } => {
+                write!(formatter, "game team index {} doesn't exist", index)
+            }
+            ScheduleError::UnknownVenueDate { venue, date } => {
+                write!(formatter, "venue {}: {} isn't a season date", venue, date)
+            }
+            ScheduleError::UnknownRestTeam(team) => {
+                write!(formatter, "rest group team {} doesn't exist", team)
+            }
+            ScheduleError::UnknownTargetTeam(team) => {
+                write!(formatter, "target games team {} doesn't exist", team)
+            }
+            ScheduleError::EmptyGrid => write!(formatter, "grid has no header row"),
+            ScheduleError::UnknownTeamName { row, name } => {
+                write!(formatter, "grid row {}: unknown team \"{}\"", row, name)
+            }
+            ScheduleError::NoAwayTeam { row, column } => write!(
+                formatter,
+                "grid row {}: column {} has a game but no away team",
+                row, column
+            ),
+            ScheduleError::GamesShort(teams) => {
+                let teams: Vec<String> = teams
+                    .iter()
+                    .map(|(team, games)| format!("{} by {}", team, games))
+                    .collect();
+                write!(
+                    formatter,
+                    "teams short of their games: {}",
+                    teams.join(", ")
+                )
+            }
+            ScheduleError::BadDate(date) => write!(formatter, "bad date \"{}\"", date),
+            ScheduleError::BadDateRange { first, last } => {
+                write!(
+                    formatter,
+                    "date range {} - {} ends before it starts",
+                    first, last
+                )
+            }
+            ScheduleError::DuplicateDate(date) => {
+                write!(formatter, "{} is listed more than once", date)
+            }
+            ScheduleError::OddTeamsPlaying { date, count } => write!(
+                formatter,
+                "{}: {} teams playing, needs to be an even number",
+                date, count
+            ),
+            ScheduleError::NotEnoughFields {
+                date,
+                games,
+                fields,
+                leagues,
+            } => write!(
+                formatter,
+                "{}: {} games but {} fields between {}",
+                date,
+                games,
+                fields,
+                leagues.join(", ")
+            ),
+            ScheduleError::DaysForTarget {
+                team,
+                days,
+                target_games,
+            } => write!(
+                formatter,
+                "{} has {} days for {} games",
+                team, days, target_games
+            ),
+            ScheduleError::BrokenRules(date) => {
+                write!(formatter, "{}: games break the schedule's rules", date)
+            }
+        }
+    }
+}
+
+impl Error for ScheduleError {}
+
+impl From<io::Error> for ScheduleError {
+    fn from(e: io::Error) -> ScheduleError {
+        ScheduleError::Io(e)
+    }
+}
+
+impl From<csv::Error> for ScheduleError {
+    fn from(e: csv::Error) -> ScheduleError {
+        ScheduleError::Csv(e)
+    }
+}
@@ -0,0 +1,144 @@
+use std::io;
+
+use crate::error::ScheduleError;
+use crate::teams::*;
+
+// Converts a published schedule grid into a list of games.
+//
+// The grid's header row has the away teams from the third column on. Every other row has the
+// date, the day of the week and then, for each away team, the home team it plays or "bye".
+//
+// ,,SL 1,SL 2,RUG 1
+// 16-Apr,Tue,,RUG 1,SL 2
+// 20-Apr,Sat,bye,,
+pub struct GridGame {
+    pub date: String,
+    pub day: String,
+    pub home: TeamId,
+    pub away: TeamId,
+}
+
+fn find_team(state: &State, name: &str, row: usize) -> Result<TeamId, ScheduleError> {
+    state
+        .team_id(name)
+        .ok_or_else(|| ScheduleError::UnknownTeamName {
+            row,
+            name: name.to_string(),
+        })
+}
+
+pub fn read_grid<R: io::Read>(reader: R, state: &State) -> Result<Vec<GridGame>, ScheduleError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+    let mut records = reader.records();
+
+    let header = records.next().ok_or(ScheduleError::EmptyGrid)??;
+    let mut away_teams: Vec<Option<TeamId>> = Vec::new();
+    for (i, name) in header.iter().enumerate() {
+        let name = name.trim_start_matches('\u{feff}').trim();
+        if i < 2 || name.is_empty() {
+            away_teams.push(None);
+        } else {
+            away_teams.push(Some(find_team(state, name, 1)?));
+        }
+    }
+
+    let mut games = Vec::new();
+    for (row, record) in records.enumerate() {
+        let record = record?;
+        let row = row + 2;
+        for (i, name) in record.iter().enumerate().skip(2) {
+            let name = name.trim();
+            if name.is_empty() || name.eq_ignore_ascii_case("bye") {
+                continue;
+            }
+            let away = match away_teams.get(i) {
+                Some(Some(away)) => *away,
+                _ => return Err(ScheduleError::NoAwayTeam { row, column: i + 1 }),
+            };
+            games.push(GridGame {
+                date: record[0].to_string(),
+                day: record[1].to_string(),
+                home: find_team(state, name, row)?,
+                away,
+            });
+        }
+    }
+    Ok(games)
+}
+
+// Writes "Date, Day, Home, Away, Host League, Type" rows. Games between teams of the same league
+// are Divisional, all others Interleague.
+pub fn write_game_list<W: io::Write>(
+    writer: W,
+    state: &State,
+    games: &[GridGame],
+) -> Result<(), ScheduleError> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(["Date", "Day", "Home", "Away", "Host League", "Type"])?;
+    for g in games.iter() {
+        let home_league = state.teams[g.home].league;
+        let away_league = state.teams[g.away].league;
+        let game_type = if home_league == away_league {
+            "Divisional"
+        } else {
+            "Interleague"
+        };
+        writer.write_record([
+            g.date.as_str(),
+            g.day.as_str(),
+            state.team_name(g.home),
+            state.team_name(g.away),
+            &state.leagues[home_league].name,
+            game_type,
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::*;
+    use crate::sample_data::get_state_from_league_info;
+
+    fn get_state() -> State {
+        get_state_from_league_info(
+            &[
+                ("SL".to_string(), 2),
+                ("RUG".to_string(), 3),
+                ("NC".to_string(), 3),
+                ("NE".to_string(), 3),
+            ],
+            10,
+        )
+    }
+
+    #[test]
+    fn grid_to_game_list() {
+        let state = get_state();
+        let games = read_grid(include_str!("../a.csv").as_bytes(), &state).unwrap();
+        let mut out = Vec::new();
+        write_game_list(&mut out, &state, &games).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let mut lines = out.lines();
+        assert_eq!(lines.next(), Some("Date,Day,Home,Away,Host League,Type"));
+        assert_eq!(lines.next(), Some("16-Apr,Tue,RUG 3,SL 2,RUG,Interleague"));
+        assert_eq!(lines.next(), Some("16-Apr,Tue,RUG 2,RUG 1,RUG,Divisional"));
+        assert_eq!(lines.next(), Some("16-Apr,Tue,SL 1,NC 2,SL,Interleague"));
+        assert!(!out.contains("bye"));
+    }
+
+    #[test]
+    fn unknown_team() {
+        let state = get_state();
+        let grid = ",,SL 1,SL 2\n16-Apr,Tue,,BAL 1\n";
+        assert!(matches!(
+            read_grid(grid.as_bytes(), &state),
+            Err(ScheduleError::UnknownTeamName { row: 2, name }) if name == "BAL 1"
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+use itertools::Itertools;
+
+use crate::interleague_schedule::*;
+use crate::teams::*;
+
+// How often each team hosts each other team: home_matrix[home][away]. Unlike the team matrix
+// this is a full matrix, not just the upper triangle.
+//
+// Every team's home count is within one of its away count, and pairs that meet more than once
+// split their games as evenly as possible.
+pub fn get_home_games(team_matrix: &Matrix) -> Matrix {
+    let num_teams = team_matrix.len();
+    let mut home_matrix = create_matrix_with_dimension(num_teams);
+
+    // Split repeat meetings evenly, leaving at most one game per pair to decide.
+    let mut edges: Vec<(usize, usize)> = Vec::new();
+    for (ti0, ti1) in (0..num_teams).tuple_combinations() {
+        let games = get_matrix_val(team_matrix, ti0, ti1);
+        home_matrix[ti0][ti1] += games / 2;
+        home_matrix[ti1][ti0] += games / 2;
+        if games % 2 == 1 {
+            edges.push((ti0, ti1));
+        }
+    }
+
+    // Connect every team with an odd number of undecided games to an extra vertex so all degrees
+    // are even. Walking closed trails then enters each vertex as often as it leaves it, and
+    // dropping the extra edge afterwards leaves a team at most one game out of balance.
+    let extra = num_teams;
+    let mut degree = vec![0; num_teams];
+    for (ti0, ti1) in edges.iter() {
+        degree[*ti0] += 1;
+        degree[*ti1] += 1;
+    }
+    for (ti, d) in degree.iter().enumerate() {
+        if d % 2 == 1 {
+            edges.push((ti, extra));
+        }
+    }
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); num_teams + 1];
+    for (ei, (v0, v1)) in edges.iter().enumerate() {
+        adjacency[*v0].push(ei);
+        adjacency[*v1].push(ei);
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut next = vec![0; num_teams + 1];
+    for start in 0..=num_teams {
+        let mut v = start;
+        loop {
+            while next[v] < adjacency[v].len() && used[adjacency[v][next[v]]] {
+                next[v] += 1;
+            }
+            if next[v] == adjacency[v].len() {
+                // All degrees are even, so a trail can only get stuck where it started.
+                break;
+            }
+            let ei = adjacency[v][next[v]];
+            used[ei] = true;
+            let w = if edges[ei].0 == v {
+                edges[ei].1
+            } else {
+                edges[ei].0
+            };
+            if v != extra && w != extra {
+                home_matrix[v][w] += 1;
+            }
+            v = w;
+        }
+    }
+    home_matrix
+}
+
+// Sets Game::home for games that are still to be scheduled, e.g. when field limits need to know
+// the host league while scheduling.
+pub fn assign_home_games(games: &mut [Game], home_matrix: &Matrix) {
+    let mut remaining = home_matrix.clone();
+    for g in games.iter_mut() {
+        let (ti0, ti1) = (g.ti0.0, g.ti1.0);
+        let home = if remaining[ti0][ti1] >= remaining[ti1][ti0] {
+            ti0
+        } else {
+            ti1
+        };
+        let away = if home == ti0 { ti1 } else { ti0 };
+        if remaining[home][away] > 0 {
+            remaining[home][away] -= 1;
+        }
+        g.home = Some(TeamId(home));
+    }
+}
+
+// Sets Game::home for every scheduled game, walking the days in order so each team alternates
+// between home and away as much as the home matrix allows. Games that already have a home team
+// keep it.
+pub fn assign_home_teams(days: &mut [Day], home_matrix: &Matrix) {
+    let mut remaining = home_matrix.clone();
+    // Home games minus away games so far, per team.
+    let mut balance = vec![0; home_matrix.len()];
+
+    for g in days.iter().flat_map(|d| d.games.iter()) {
+        if g.home.is_some() {
+            let (TeamId(home), TeamId(away)) = g.home_away();
+            if remaining[home][away] > 0 {
+                remaining[home][away] -= 1;
+            }
+        }
+    }
+
+    for day in days.iter_mut() {
+        for g in day.games.iter_mut() {
+            if g.home.is_some() {
+                let (TeamId(home), TeamId(away)) = g.home_away();
+                balance[home] += 1;
+                balance[away] -= 1;
+                continue;
+            }
+            let (ti0, ti1) = (g.ti0.0, g.ti1.0);
+            let ti0_first = balance[ti0] <= balance[ti1];
+            let home = match (remaining[ti0][ti1] > 0, remaining[ti1][ti0] > 0) {
+                (true, false) => ti0,
+                (false, true) => ti1,
+                // Both directions are left, or the game isn't in the home matrix at all.
+                _ => {
+                    if ti0_first {
+                        ti0
+                    } else {
+                        ti1
+                    }
+                }
+            };
+            let away = if home == ti0 { ti1 } else { ti0 };
+            if remaining[home][away] > 0 {
+                remaining[home][away] -= 1;
+            }
+            balance[home] += 1;
+            balance[away] -= 1;
+            g.home = Some(TeamId(home));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::home_away::*;
+    use crate::sample_data::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn balanced() {
+        let mut state = get_2023_aaa_softball_state();
+        let league_distance_matrix = get_district8_city_league_distances();
+        state.set_target_games(14);
+        let team_matrix = get_teams_to_play_against(&mut state, &league_distance_matrix).unwrap();
+        let home_matrix = get_home_games(&team_matrix);
+
+        for ti0 in 0..state.teams.len() {
+            let home: i32 = home_matrix[ti0].iter().sum();
+            let away: i32 = home_matrix.iter().map(|row| row[ti0]).sum();
+            assert_eq!(home + away, state.teams[ti0].num_games);
+            assert!(
+                (home - away).abs() <= 1,
+                "team {}: {} - {}",
+                ti0,
+                home,
+                away
+            );
+
+            for (ti1, ti0_home) in home_matrix[ti0].iter().enumerate() {
+                let games = get_matrix_val(&team_matrix, ti0, ti1);
+                if ti0 != ti1 {
+                    assert_eq!(ti0_home + home_matrix[ti1][ti0], games);
+                    assert!((ti0_home - home_matrix[ti1][ti0]).abs() <= 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn assign_days() {
+        let mut team_matrix = create_matrix_with_dimension(2);
+        team_matrix[0][1] = 2;
+        let home_matrix = get_home_games(&team_matrix);
+        let game = Game {
+            ti0: TeamId(0),
+            ti1: TeamId(1),
+            distance: 0,
+            home: None,
+        };
+        let mut days: Vec<Day> = [23, 27]
+            .iter()
+            .map(|day| {
+                let date = NaiveDate::from_ymd_opt(2023, 3, *day).unwrap();
+                let mut day = Day::new(date, Default::default());
+                day.games.push(game);
+                day
+            })
+            .collect();
+        assign_home_teams(&mut days, &home_matrix);
+        assert_eq!(days[0].games[0].home, Some(TeamId(0)));
+        assert_eq!(days[1].games[0].home, Some(TeamId(1)));
+    }
+}
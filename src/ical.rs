@@ -0,0 +1,219 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+
+use crate::error::ScheduleError;
+use crate::interleague_schedule::*;
+use crate::teams::*;
+
+// iCalendar (RFC 5545) files for a solved schedule, one per team and one per league, so coaches
+// and parents can subscribe to their games. Games are all-day events since days have no start
+// times. DTSTAMP is when the files were generated, passed in so output can be reproduced.
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Content lines are folded at 75 octets, continuing with a leading space.
+fn push_line(ics: &mut String, line: &str) {
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            ics.push_str("\r\n ");
+            len = 1;
+        }
+        ics.push(c);
+        len += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+// Where a league hosts its games: its venue names, or the league name if none are listed.
+pub fn get_location(league: &League) -> String {
+    if league.venues.is_empty() {
+        return league.name.clone();
+    }
+    league
+        .venues
+        .iter()
+        .map(|v| v.name.as_str())
+        .collect::<Vec<&str>>()
+        .join(" / ")
+}
+
+fn push_event(
+    ics: &mut String,
+    state: &State,
+    day: &Day,
+    game: &Game,
+    team: Option<TeamId>,
+    stamp: NaiveDateTime,
+) {
+    let (home, away) = game.home_away();
+    let host_league = &state.leagues[state.teams[home].league];
+    let date = day.date.format("%Y%m%d");
+    let summary = match team {
+        Some(id) if id == home => format!(
+            "{} vs {} (home)",
+            state.team_name(home),
+            state.team_name(away)
+        ),
+        Some(_) => format!(
+            "{} @ {} (away)",
+            state.team_name(away),
+            state.team_name(home)
+        ),
+        None => format!("{} @ {}", state.team_name(away), state.team_name(home)),
+    };
+
+    push_line(ics, "BEGIN:VEVENT");
+    push_line(
+        ics,
+        &format!(
+            "UID:{}-{}-{}@interleague-schedule",
+            date,
+            state.team_name(home).replace(' ', ""),
+            state.team_name(away).replace(' ', "")
+        ),
+    );
+    push_line(ics, &format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")));
+    push_line(ics, &format!("DTSTART;VALUE=DATE:{}", date));
+    push_line(ics, &format!("SUMMARY:{}", escape(&summary)));
+    push_line(
+        ics,
+        &format!(
+            "DESCRIPTION:{}",
+            escape(&format!(
+                "Home: {}\nAway: {}\nHost league: {}",
+                state.team_name(home),
+                state.team_name(away),
+                host_league.name
+            ))
+        ),
+    );
+    push_line(
+        ics,
+        &format!("LOCATION:{}", escape(&get_location(host_league))),
+    );
+    push_line(ics, "END:VEVENT");
+}
+
+fn get_calendar<F: Fn(&Game) -> bool>(
+    days: &[Day],
+    state: &State,
+    name: &str,
+    team: Option<TeamId>,
+    stamp: NaiveDateTime,
+    include: F,
+) -> String {
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//interleague-schedule//EN");
+    push_line(&mut ics, &format!("X-WR-CALNAME:{}", escape(name)));
+    for day in days.iter() {
+        for game in day.games.iter().filter(|g| include(g)) {
+            push_event(&mut ics, state, day, game, team, stamp);
+        }
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+pub fn get_team_calendar(days: &[Day], state: &State, id: TeamId, stamp: NaiveDateTime) -> String {
+    get_calendar(days, state, state.team_name(id), Some(id), stamp, |g| {
+        g.ti0 == id || g.ti1 == id
+    })
+}
+
+// Every game involving one of the league's teams.
+pub fn get_league_calendar(
+    days: &[Day],
+    state: &State,
+    id: LeagueId,
+    stamp: NaiveDateTime,
+) -> String {
+    let league = &state.leagues[id];
+    get_calendar(days, state, &league.name, None, stamp, |g| {
+        state.teams[g.ti0].league == id || state.teams[g.ti1].league == id
+    })
+}
+
+// Writes "<team>.ics" for every team and "<league>.ics" for every league into dir.
+pub fn write_calendars(
+    dir: &Path,
+    days: &[Day],
+    state: &State,
+    stamp: NaiveDateTime,
+) -> Result<(), ScheduleError> {
+    fs::create_dir_all(dir)?;
+    for team in state.teams.iter() {
+        let file_name = format!("{}.ics", team.name.replace(' ', "_"));
+        fs::write(
+            dir.join(file_name),
+            get_team_calendar(days, state, team.id, stamp),
+        )?;
+    }
+    for league in state.leagues.iter() {
+        let file_name = format!("{}.ics", league.name.replace(' ', "_"));
+        fs::write(
+            dir.join(file_name),
+            get_league_calendar(days, state, league.id, stamp),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ical::*;
+    use crate::sample_data::get_state_from_league_info;
+    use crate::team_set::TeamSet;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn calendars() {
+        let state = get_state_from_league_info(&[("SL".to_string(), 1), ("NE".to_string(), 3)], 1);
+        let game = |ti0, ti1, home| Game {
+            ti0: TeamId(ti0),
+            ti1: TeamId(ti1),
+            distance: 0,
+            home: Some(TeamId(home)),
+        };
+        let mut day = Day::new(NaiveDate::from_ymd_opt(2023, 4, 3).unwrap(), TeamSet::new());
+        day.games = vec![game(0, 1, 1), game(2, 3, 2)];
+        let days = vec![day];
+        let stamp = NaiveDate::from_ymd_opt(2023, 1, 2)
+            .unwrap()
+            .and_hms_opt(3, 4, 5)
+            .unwrap();
+
+        let ics = get_team_calendar(&days, &state, TeamId(0), stamp);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains("DTSTAMP:20230102T030405Z\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20230403\r\n"));
+        assert!(ics.contains("SUMMARY:SL 1 @ NE 1 (away)\r\n"));
+        assert!(ics.contains("DESCRIPTION:Home: NE 1\\nAway: SL 1\\nHost league: NE\r\n"));
+        assert!(ics.contains("LOCATION:NE\r\n"));
+
+        let ics = get_league_calendar(&days, &state, LeagueId(1), stamp);
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("SUMMARY:NE 3 @ NE 2\r\n"));
+    }
+
+    #[test]
+    fn fold() {
+        let mut ics = String::new();
+        push_line(&mut ics, &"x".repeat(100));
+        assert_eq!(
+            ics,
+            format!("{}\r\n {}\r\n", "x".repeat(75), "x".repeat(25))
+        );
+    }
+}
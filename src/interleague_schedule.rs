@@ -1,64 +1,683 @@
 use backtrack::Config;
-use std::collections::{HashMap, HashSet};
+use chrono::{Datelike, NaiveDate, Weekday};
+use std::collections::HashMap;
 use std::fmt;
+use std::rc::Rc;
+
+use crate::error::ScheduleError;
+use crate::team_set::TeamSet;
+use crate::teams::*;
 
 #[derive(Copy, Clone, Debug)]
 pub struct Game {
-    pub ti0: usize,
-    pub ti1: usize,
+    pub ti0: TeamId,
+    pub ti1: TeamId,
     pub distance: i32,
+    // Set once home teams are assigned, see home_away.
+    pub home: Option<TeamId>,
+}
+
+impl Game {
+    // (home, away). ti0 is home until home teams are assigned.
+    pub fn home_away(&self) -> (TeamId, TeamId) {
+        match self.home {
+            Some(home) if home == self.ti1 => (self.ti1, self.ti0),
+            _ => (self.ti0, self.ti1),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Day {
-    pub date: String,
-    pub is_weekend: bool,
-    pub teams_playing: HashSet<usize>,
+    pub date: NaiveDate,
+    pub teams_playing: TeamSet,
     pub games: Vec<Game>,
+    // Games each league can still host this day. Leagues without venues aren't listed and can host
+    // any number of games.
+    pub fields_available: HashMap<LeagueId, usize>,
+}
+
+impl Day {
+    pub fn new(date: NaiveDate, teams_playing: TeamSet) -> Day {
+        Day {
+            date,
+            teams_playing,
+            games: vec![],
+            fields_available: HashMap::new(),
+        }
+    }
+
+    pub fn is_weekend(&self) -> bool {
+        matches!(self.date.weekday(), Weekday::Sat | Weekday::Sun)
+    }
+}
+
+// Games scheduled so far, newest first. Configs in the search share all but their newest games,
+// so scheduling a game adds a node instead of copying every day.
+struct Scheduled {
+    days_index: usize,
+    game: Game,
+    previous: Option<Rc<Scheduled>>,
 }
 
+// A point in the search. Anything that only changes from one day to the next is shared between
+// configs and copied once per day, not once per game.
 #[derive(Clone)]
 pub struct ScheduleConfig {
-    pub days: Vec<Day>,
-    pub days_index: usize,
-    pub remaining_games: Vec<Game>,
+    // Days as given, in date order.
+    days: Rc<Vec<Day>>,
+    days_index: usize,
+    // Teams on the current day still to be paired, and those that have played.
+    teams_left: TeamSet,
+    teams_played: TeamSet,
+    // Games each league can still host on the current day, None for any number.
+    fields_left: Rc<Vec<Option<usize>>>,
+    scheduled: Option<Rc<Scheduled>>,
+    // Every distinct game to schedule, and how many of each are still remaining. Repeat meetings
+    // with the same home team are interchangeable, so they're counted rather than listed, and the
+    // search never tries one copy and then the other.
+    games: Rc<Vec<Game>>,
+    remaining: Vec<usize>,
+    num_remaining: usize,
+    // Games that could be played on the current day as it started: remaining, both teams
+    // playing that day and not a rematch too soon. The search only has to recheck what changes
+    // during the day.
+    day_games: Rc<Vec<usize>>,
+    // Remaining games per team.
+    team_games_left: Vec<usize>,
+    // Games per team over the whole season, scheduled or not.
+    team_games: Rc<Vec<usize>>,
+    // Per day index, the days from there on that each team plays, and how many are weekends. One
+    // row longer than days.
+    team_days_left: Rc<Vec<Vec<usize>>>,
+    team_weekends_left: Rc<Vec<Vec<usize>>>,
+    // League of every team, for finding a game's host league.
+    team_leagues: Rc<Vec<LeagueId>>,
+    rest: Rc<Rest>,
+    // Every team along with the teams sharing a rest group with it.
+    rest_mates: Rc<Vec<TeamSet>>,
+    // Teams that can sit out days they're available for, see Rest::can_rest.
+    can_rest: TeamSet,
+    // The rest are as of the start of the current day. Two teams can't meet twice in a day, and
+    // is_resting counts games on the current day itself.
+    //
+    // Date of every team's latest game.
+    last_games: Rc<Vec<Option<NaiveDate>>>,
+    weekend_tolerance: Option<usize>,
+    // Weekend games per team.
+    weekend_games: Rc<Vec<usize>>,
+    rematch_min_days: usize,
+    // Date of the latest meeting of every pair of teams that has met, keyed smallest team first.
+    last_meetings: Rc<HashMap<(TeamId, TeamId), NaiveDate>>,
 }
 
-fn validate_days(days: &Vec<Day>) -> bool {
-    // Verify same number of games for all teams.
-    let mut hm: HashMap<usize, i32> = HashMap::new();
+// One Game per meeting in the team matrix, closest games first.
+pub fn get_remaining_games(
+    state: &State,
+    team_matrix: &Matrix,
+    league_distance_matrix: &Matrix,
+) -> Vec<Game> {
+    let mut remaining_games: Vec<Game> = Vec::new();
+    for ti0 in 0..state.teams.len() {
+        for ti1 in ti0 + 1..state.teams.len() {
+            for _ in 0..get_matrix_val(team_matrix, ti0, ti1) {
+                let distance = get_matrix_val(
+                    league_distance_matrix,
+                    state.teams[ti0].league.0,
+                    state.teams[ti1].league.0,
+                );
+                remaining_games.push(Game {
+                    ti0: TeamId(ti0),
+                    ti1: TeamId(ti1),
+                    distance,
+                    home: None,
+                });
+            }
+        }
+    }
+    remaining_games.sort_by_key(|g| g.distance);
+    remaining_games
+}
+
+// Leagues that could host a game on day, which is any league with a team playing.
+fn get_host_leagues(day: &Day, state: &State) -> Vec<LeagueId> {
+    let mut leagues: Vec<LeagueId> = day
+        .teams_playing
+        .iter()
+        .map(|id| state.teams[id].league)
+        .collect();
+    leagues.sort();
+    leagues.dedup();
+    leagues
+}
+
+// Most games day's host leagues have fields for, or None if one of them has no venues listed.
+pub fn get_field_capacity(day: &Day, state: &State) -> Option<usize> {
+    get_host_leagues(day, state)
+        .iter()
+        .map(|l| state.leagues[*l].fields_on(day.date))
+        .sum()
+}
+
+// Every day has to be filled, so each team needs as many days as its target games, and each day
+// needs fields for every team playing. Teams with rest rules can sit days out, so they need at
+// least that many days, and a day with one of them playing may need fewer fields.
+fn validate_days(days: &[Day], state: &State) -> Result<(), ScheduleError> {
+    if days.is_empty() {
+        return Err(ScheduleError::NoDays);
+    }
+    let mut counts = vec![0; state.teams.len()];
     for d in days.iter() {
-        for u in d.teams_playing.iter() {
-            *hm.entry(*u).or_insert(0) += 1;
+        if let Some(id) = d.teams_playing.iter().find(|id| id.0 >= state.teams.len()) {
+            return Err(ScheduleError::UnknownTeamIndex {
+                date: Some(d.date),
+                index: id.0,
+            });
+        }
+        if d.teams_playing.len() % 2 != 0 {
+            return Err(ScheduleError::OddTeamsPlaying {
+                date: d.date,
+                count: d.teams_playing.len(),
+            });
         }
+        for id in d.teams_playing.iter() {
+            counts[id.0] += 1;
+        }
+        let games = d.teams_playing.len() / 2;
+        let can_rest = d.teams_playing.iter().any(|id| state.rest.can_rest(id));
+        match get_field_capacity(d, state) {
+            Some(fields) if fields < games && !can_rest => {
+                return Err(ScheduleError::NotEnoughFields {
+                    date: d.date,
+                    games,
+                    fields,
+                    leagues: get_host_leagues(d, state)
+                        .iter()
+                        .map(|l| state.leagues[*l].name.clone())
+                        .collect(),
+                })
+            }
+            _ => (),
+        }
+    }
+    let wrong_days = |ti: usize| {
+        let target = state.teams[ti].target_games as usize;
+        counts[ti] < target || (counts[ti] > target && !state.rest.can_rest(TeamId(ti)))
+    };
+    match (0..state.teams.len()).find(|ti| wrong_days(*ti)) {
+        Some(ti) => Err(ScheduleError::DaysForTarget {
+            team: state.teams[ti].name.clone(),
+            days: counts[ti],
+            target_games: state.teams[ti].target_games,
+        }),
+        None => Ok(()),
     }
-    let val = hm.get(&0).unwrap();
-    return hm.values().all(|game_count| game_count == val);
 }
 
 impl ScheduleConfig {
-    pub fn new(days: Vec<Day>, remaining_games: Vec<Game>) -> ScheduleConfig {
-        assert!(validate_days(&days), "bad days");
-        ScheduleConfig {
-            days,
+    // Days are scheduled in date order. Games with a home team set are hosted by that team's
+    // league, all others by ti0's league.
+    pub fn new(
+        mut days: Vec<Day>,
+        remaining_games: Vec<Game>,
+        state: &State,
+    ) -> Result<ScheduleConfig, ScheduleError> {
+        if state.teams.len() > TeamSet::MAX_TEAMS {
+            return Err(ScheduleError::TooManyTeams(state.teams.len()));
+        }
+        validate_days(&days, state)?;
+        let game_teams = remaining_games
+            .iter()
+            .flat_map(|g| [Some(g.ti0), Some(g.ti1), g.home]);
+        if let Some(id) = game_teams.flatten().find(|id| id.0 >= state.teams.len()) {
+            return Err(ScheduleError::UnknownTeamIndex {
+                date: None,
+                index: id.0,
+            });
+        }
+        days.sort_by_key(|d| d.date);
+        for day in days.iter_mut() {
+            for league in state.leagues.iter() {
+                if let Some(fields) = league.fields_on(day.date) {
+                    day.fields_available.insert(league.id, fields);
+                }
+            }
+        }
+        let mut games: Vec<Game> = Vec::new();
+        let mut remaining = Vec::new();
+        let mut team_games_left = vec![0; state.teams.len()];
+        for g in remaining_games.iter() {
+            let same = |other: &Game| (other.ti0, other.ti1, other.home) == (g.ti0, g.ti1, g.home);
+            match games.iter().position(same) {
+                Some(gi) => remaining[gi] += 1,
+                None => {
+                    games.push(*g);
+                    remaining.push(1);
+                }
+            }
+            team_games_left[g.ti0.0] += 1;
+            team_games_left[g.ti1.0] += 1;
+        }
+        let mut team_days_left = vec![vec![0; state.teams.len()]; days.len() + 1];
+        let mut team_weekends_left = team_days_left.clone();
+        for (i, day) in days.iter().enumerate().rev() {
+            team_days_left[i] = team_days_left[i + 1].clone();
+            team_weekends_left[i] = team_weekends_left[i + 1].clone();
+            for id in day.teams_playing.iter() {
+                team_days_left[i][id.0] += 1;
+                if day.is_weekend() {
+                    team_weekends_left[i][id.0] += 1;
+                }
+            }
+        }
+        let rest_mates = state
+            .teams
+            .iter()
+            .map(|team| {
+                let mut mates = TeamSet::new();
+                mates.insert(team.id);
+                for group in state.rest.groups.iter() {
+                    if group.teams.contains(&team.id) {
+                        for id in group.teams.iter() {
+                            mates.insert(*id);
+                        }
+                    }
+                }
+                mates
+            })
+            .collect();
+        let mut config = ScheduleConfig {
+            days: Rc::new(days),
             days_index: 0,
-            remaining_games,
+            teams_left: TeamSet::new(),
+            teams_played: TeamSet::new(),
+            fields_left: Rc::new(vec![]),
+            scheduled: None,
+            num_remaining: remaining_games.len(),
+            games: Rc::new(games),
+            remaining,
+            day_games: Rc::new(vec![]),
+            team_games: Rc::new(team_games_left.clone()),
+            team_games_left,
+            team_days_left: Rc::new(team_days_left),
+            team_weekends_left: Rc::new(team_weekends_left),
+            team_leagues: Rc::new(state.teams.iter().map(|t| t.league).collect()),
+            rest: Rc::new(state.rest.clone()),
+            rest_mates: Rc::new(rest_mates),
+            can_rest: state
+                .teams
+                .iter()
+                .map(|t| t.id)
+                .filter(|id| state.rest.can_rest(*id))
+                .collect(),
+            last_games: Rc::new(vec![None; state.teams.len()]),
+            weekend_tolerance: state.weekend_tolerance,
+            weekend_games: Rc::new(vec![0; state.teams.len()]),
+            rematch_min_days: state.rematch_min_days,
+            last_meetings: Rc::new(HashMap::new()),
+        };
+        config.start_day(state.leagues.len());
+        Ok(config)
+    }
+
+    // A config with every day filled from games already placed on days, such as a schedule
+    // improved outside the search, with the unscheduled games remaining. The games are replayed
+    // through the search's own checks, so this errors with the first day the search couldn't
+    // have produced.
+    pub fn from_days(
+        days: &[Day],
+        unscheduled: Vec<Game>,
+        state: &State,
+    ) -> Result<ScheduleConfig, ScheduleError> {
+        let place_all_games = unscheduled.is_empty();
+        let mut games = unscheduled;
+        games.extend(days.iter().flat_map(|d| d.games.iter().copied()));
+        let empty_days = days
+            .iter()
+            .map(|d| Day {
+                games: vec![],
+                ..d.clone()
+            })
+            .collect();
+        let mut config = ScheduleConfig::new(empty_days, games, state)?;
+        let mut days: Vec<&Day> = days.iter().collect();
+        days.sort_by_key(|d| d.date);
+        for day in days {
+            let broken = || ScheduleError::BrokenRules(day.date);
+            for game in day.games.iter() {
+                config.sit_out_resting(day.date);
+                let gi = config
+                    .games
+                    .iter()
+                    .position(|g| (g.ti0, g.ti1, g.home) == (game.ti0, game.ti1, game.home))
+                    .unwrap();
+                if !config.can_schedule(gi, day.date) {
+                    return Err(broken());
+                }
+                config = config.with_game(gi);
+            }
+            // Every team that isn't resting has to have played or be able to sit out.
+            config.sit_out_resting(day.date);
+            if !config.teams_left.iter().all(|id| config.can_sit_out(id)) {
+                return Err(broken());
+            }
+            config.teams_left = TeamSet::new();
+            config = ScheduleConfig::from_next_day(&config);
+            if !config.is_valid_for(place_all_games) {
+                return Err(broken());
+            }
         }
+        Ok(config)
     }
 
-    fn from_next_day(old_config: &ScheduleConfig) -> ScheduleConfig {
+    // Teams left on the current day that have to sit it out.
+    fn resting_teams(&self, date: NaiveDate) -> TeamSet {
+        self.teams_left
+            .iter()
+            .filter(|id| self.is_resting(*id, date))
+            .collect()
+    }
+
+    // Takes resting teams out of the teams left to play, as the search does.
+    fn sit_out_resting(&mut self, date: NaiveDate) {
+        self.teams_left = self.teams_left.difference(&self.resting_teams(date));
+    }
+
+    // Sets up the current day's teams, fields and games.
+    fn start_day(&mut self, num_leagues: usize) {
+        self.teams_played = TeamSet::new();
+        match self.days.get(self.days_index) {
+            Some(day) => {
+                self.teams_left = day.teams_playing;
+                self.fields_left = Rc::new(
+                    (0..num_leagues)
+                        .map(|li| day.fields_available.get(&LeagueId(li)).copied())
+                        .collect(),
+                );
+                self.day_games = Rc::new(
+                    self.remaining_indices()
+                        .filter(|gi| self.can_schedule(*gi, day.date))
+                        .collect(),
+                );
+            }
+            None => {
+                self.teams_left = TeamSet::new();
+                self.day_games = Rc::new(vec![]);
+            }
+        }
+    }
+
+    // Every day has been filled, whether or not games remain.
+    pub fn is_out_of_days(&self) -> bool {
+        self.days_index == self.days.len()
+    }
+
+    pub fn days_index(&self) -> usize {
+        self.days_index
+    }
+
+    // Every day with the games scheduled on it. The current day lists the teams still to be paired,
+    // earlier days none.
+    pub fn days(&self) -> Vec<Day> {
+        let mut days = self.scheduled_days();
+        for (i, day) in days.iter_mut().enumerate() {
+            if i < self.days_index {
+                day.teams_playing = TeamSet::new();
+            } else if i == self.days_index {
+                day.teams_playing = self.teams_left;
+            }
+        }
+        days
+    }
+
+    // Every day with the games scheduled on it, listing every team that plays it.
+    pub fn scheduled_days(&self) -> Vec<Day> {
+        let mut days: Vec<Day> = self.days.to_vec();
+        let mut node = &self.scheduled;
+        while let Some(scheduled) = node {
+            days[scheduled.days_index].games.push(scheduled.game);
+            node = &scheduled.previous;
+        }
+        for day in days.iter_mut() {
+            day.games.reverse();
+        }
+        days
+    }
+
+    pub fn remaining_games(&self) -> Vec<Game> {
+        self.remaining_indices()
+            .flat_map(|gi| std::iter::repeat_n(self.games[gi], self.remaining[gi]))
+            .collect()
+    }
+
+    fn remaining_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.games.len()).filter(|gi| self.remaining[*gi] > 0)
+    }
+
+    pub fn num_teams(&self) -> usize {
+        self.team_leagues.len()
+    }
+
+    // Whether ti, or a team sharing coaches or pitchers with it, played too recently for ti to
+    // play on date.
+    pub fn is_resting(&self, ti: TeamId, date: NaiveDate) -> bool {
+        if !self.can_rest.contains(ti) {
+            return false;
+        }
+        let last_game = |other: TeamId| {
+            if self.teams_played.contains(other) {
+                Some(self.days[self.days_index].date)
+            } else {
+                self.last_games[other.0]
+            }
+        };
+        let too_soon = |other: TeamId, min_days: usize| match last_game(other) {
+            Some(last) => (date - last).num_days() - 1 < min_days as i64,
+            None => false,
+        };
+        too_soon(ti, self.rest.min_days)
+            || self
+                .rest
+                .groups
+                .iter()
+                .filter(|g| g.teams.contains(&ti))
+                .any(|g| g.teams.iter().any(|other| too_soon(*other, g.min_days)))
+    }
+
+    // Whether every team can still end up within weekend_tolerance weekend games of each other.
+    // Teams with fewer games are compared by their share of weekend games, scaled up to the most
+    // games any team plays, so tolerance is in games of the team with the most games. With equal
+    // targets that's a plain count, and weekday games balance too.
+    fn is_weekend_balance_possible(&self) -> bool {
+        let tolerance = match self.weekend_tolerance {
+            Some(tolerance) => tolerance,
+            None => return true,
+        };
+        let today_weekend = !self.is_out_of_days() && self.days[self.days_index].is_weekend();
+        let weekends_after = self.team_weekends_left.get(self.days_index + 1);
+        let most_games = self.team_games.iter().copied().max().unwrap_or(0);
+        let share = |ti: usize, games: usize| {
+            games.min(self.team_games[ti]) as f64 * most_games as f64 / self.team_games[ti] as f64
+        };
+        let mut most = 0.0;
+        let mut fewest_possible = f64::INFINITY;
+        for ti in (0..self.num_teams()).filter(|ti| self.team_games[*ti] > 0) {
+            let id = TeamId(ti);
+            let mut games = self.weekend_games[ti];
+            let mut possible = games + weekends_after.map_or(0, |w| w[ti]);
+            if today_weekend && self.teams_played.contains(id) {
+                games += 1;
+                possible += 1;
+            } else if today_weekend && self.teams_left.contains(id) {
+                possible += 1;
+            }
+            most = f64::max(most, share(ti, games));
+            fewest_possible = f64::min(fewest_possible, share(ti, possible));
+        }
+        most - fewest_possible <= tolerance as f64 + 1e-9
+    }
+
+    // Whether a remaining game can be scheduled on the current day.
+    fn can_schedule(&self, gi: usize, date: NaiveDate) -> bool {
+        self.is_open(gi) && !self.is_rematch_too_soon(&self.games[gi], date)
+    }
+
+    // Whether a game's teams are both still to play on the current day, with a field left to
+    // host it.
+    fn is_open(&self, gi: usize) -> bool {
+        let game = &self.games[gi];
+        let host_league = self.team_leagues[game.home_away().0 .0];
+        self.teams_left.contains(game.ti0)
+            && self.teams_left.contains(game.ti1)
+            && self.fields_left[host_league.0] != Some(0)
+    }
+
+    // Forward checking: every team's remaining games have to fit in the days it has left, and a
+    // team that can't rest needs a game for each of those days. Leftover games are fine when only
+    // filling days.
+    fn can_finish(&self, place_all_games: bool) -> bool {
+        let days_left = self.team_days_left.get(self.days_index + 1);
+        (0..self.num_teams()).all(|ti| {
+            let days =
+                days_left.map_or(0, |d| d[ti]) + self.teams_left.contains(TeamId(ti)) as usize;
+            let games = self.team_games_left[ti];
+            let can_rest = self.can_rest.contains(TeamId(ti));
+            (!place_all_games || games <= days) && (can_rest || games >= days)
+        })
+    }
+
+    // Whether a team left on the current day can sit it out without resting, because it has
+    // rest rules and more days left than games.
+    fn can_sit_out(&self, id: TeamId) -> bool {
+        self.can_rest.contains(id)
+            && self.team_days_left[self.days_index][id.0] > self.team_games_left[id.0]
+    }
+
+    // is_valid, for either the full search or FillDays. Teams that aren't resting have to pair
+    // up, unless one of them can sit out.
+    fn is_valid_for(&self, place_all_games: bool) -> bool {
+        if !self.is_weekend_balance_possible() || !self.can_finish(place_all_games) {
+            return false;
+        }
+        if self.is_out_of_days() {
+            return true;
+        }
+        let date = self.days[self.days_index].date;
+        let playing: TeamSet = self
+            .teams_left
+            .iter()
+            .filter(|id| !self.is_resting(*id, date))
+            .collect();
+        playing.len().is_multiple_of(2) || playing.iter().any(|id| self.can_sit_out(id))
+    }
+
+    fn last_meeting(&self, game: &Game) -> Option<NaiveDate> {
+        let key = (game.ti0.min(game.ti1), game.ti0.max(game.ti1));
+        self.last_meetings.get(&key).copied()
+    }
+
+    // Whether a game's teams met too recently to meet again on date. Like rest, rematch_min_days
+    // counts the days between the two meetings, not including either.
+    fn is_rematch_too_soon(&self, game: &Game, date: NaiveDate) -> bool {
+        match self.last_meeting(game) {
+            Some(last) => (date - last).num_days() - 1 < self.rematch_min_days as i64,
+            None => false,
+        }
+    }
+
+    // Halfway between the first and last days.
+    fn midseason(&self) -> NaiveDate {
+        let first = self.days[0].date;
+        let last = self.days[self.days.len() - 1].date;
+        first + (last - first) / 2
+    }
+
+    // Schedules a remaining game on the current day.
+    fn with_game(&self, gi: usize) -> ScheduleConfig {
+        let game = &self.games[gi];
+        let host_league = self.team_leagues[game.home_away().0 .0];
+        let mut teams_left = self.teams_left;
+        teams_left.remove(game.ti0);
+        teams_left.remove(game.ti1);
+        let mut teams_played = self.teams_played;
+        teams_played.insert(game.ti0);
+        teams_played.insert(game.ti1);
+        let mut fields_left = self.fields_left.clone();
+        if let Some(fields) = fields_left[host_league.0] {
+            Rc::make_mut(&mut fields_left)[host_league.0] = Some(fields - 1);
+        }
+        let mut remaining = self.remaining.clone();
+        remaining[gi] -= 1;
+        let mut team_games_left = self.team_games_left.clone();
+        team_games_left[game.ti0.0] -= 1;
+        team_games_left[game.ti1.0] -= 1;
+
         ScheduleConfig {
-            days: old_config.days.to_vec(),
-            days_index: old_config.days_index + 1,
-            remaining_games: old_config.remaining_games.to_vec(),
+            days: self.days.clone(),
+            days_index: self.days_index,
+            teams_left,
+            teams_played,
+            fields_left,
+            scheduled: Some(Rc::new(Scheduled {
+                days_index: self.days_index,
+                game: *game,
+                previous: self.scheduled.clone(),
+            })),
+            games: self.games.clone(),
+            remaining,
+            num_remaining: self.num_remaining - 1,
+            day_games: self.day_games.clone(),
+            team_games_left,
+            team_games: self.team_games.clone(),
+            team_days_left: self.team_days_left.clone(),
+            team_weekends_left: self.team_weekends_left.clone(),
+            team_leagues: self.team_leagues.clone(),
+            rest: self.rest.clone(),
+            rest_mates: self.rest_mates.clone(),
+            can_rest: self.can_rest,
+            last_games: self.last_games.clone(),
+            weekend_tolerance: self.weekend_tolerance,
+            weekend_games: self.weekend_games.clone(),
+            rematch_min_days: self.rematch_min_days,
+            last_meetings: self.last_meetings.clone(),
         }
     }
+
+    // Moves on to the next day, counting the current day's games towards rest, weekend balance
+    // and rematches.
+    fn from_next_day(old_config: &ScheduleConfig) -> ScheduleConfig {
+        let mut config = old_config.clone();
+        let day = &old_config.days[old_config.days_index];
+        if !old_config.teams_played.is_empty() {
+            let last_games = Rc::make_mut(&mut config.last_games);
+            let weekend_games = Rc::make_mut(&mut config.weekend_games);
+            for id in old_config.teams_played.iter() {
+                last_games[id.0] = Some(day.date);
+                if day.is_weekend() {
+                    weekend_games[id.0] += 1;
+                }
+            }
+            let last_meetings = Rc::make_mut(&mut config.last_meetings);
+            let mut node = &old_config.scheduled;
+            while let Some(scheduled) = node {
+                if scheduled.days_index != old_config.days_index {
+                    break;
+                }
+                let game = scheduled.game;
+                last_meetings.insert((game.ti0.min(game.ti1), game.ti0.max(game.ti1)), day.date);
+                node = &scheduled.previous;
+            }
+        }
+        config.days_index += 1;
+        config.start_day(old_config.fields_left.len());
+        config
+    }
 }
 
 impl fmt::Debug for ScheduleConfig {
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(formatter, "ScheduleConfig:\n")?;
-        for (i, day) in self.days.iter().enumerate() {
+        for (i, day) in self.days().iter().enumerate() {
             write!(formatter, "{}. {}: ", i, day.date)?;
             let mut day_distance = 0;
             for g in day.games.iter() {
@@ -66,11 +685,8 @@ impl fmt::Debug for ScheduleConfig {
                 write!(formatter, "({}, {}), ", g.ti0, g.ti1)?;
             }
             write!(formatter, "dist: {}, remaining teams: ", day_distance)?;
-            let mut v = Vec::from_iter(day.teams_playing.clone());
-            v.sort();
-            write!(formatter, "{:?}, ", v)?;
+            write!(formatter, "{:?}, ", day.teams_playing)?;
             write!(formatter, "\n")?;
-            // write!(formatter, "remaining games: {:?}\n", self.remaining_games)?;
         }
         Ok(())
     }
@@ -78,56 +694,486 @@ impl fmt::Debug for ScheduleConfig {
 
 impl Config for ScheduleConfig {
     fn successors(&self) -> Vec<ScheduleConfig> {
-        if self.days[self.days_index].teams_playing.is_empty() {
-            return vec![ScheduleConfig::from_next_day(&self)];
-        }
-
-        let mut successors = Vec::with_capacity(self.remaining_games.len());
-
-        for (gi, game) in self.remaining_games.iter().enumerate() {
-            if self.days[self.days_index].teams_playing.contains(&game.ti0)
-                && self.days[self.days_index].teams_playing.contains(&game.ti1)
-            {
-                let mut new_remaining_games = self.remaining_games.to_vec();
-                let next_game = new_remaining_games.remove(gi);
-                let mut new_days = self.days.clone();
-                new_days[self.days_index]
-                    .teams_playing
-                    .remove(&next_game.ti0);
-                new_days[self.days_index]
-                    .teams_playing
-                    .remove(&next_game.ti1);
-                new_days[self.days_index].games.push(next_game);
-
-                if new_remaining_games.len() < new_days[self.days_index].teams_playing.len() / 2 {
-                    break;
-                }
-                successors.push(ScheduleConfig {
-                    days: new_days,
-                    days_index: self.days_index,
-                    remaining_games: new_remaining_games,
-                });
+        if self.is_out_of_days() {
+            return vec![];
+        }
+        if self.teams_left.is_empty() {
+            return vec![ScheduleConfig::from_next_day(self)];
+        }
+
+        // Resting teams sit the day out.
+        let date = self.days[self.days_index].date;
+        let resting = self.resting_teams(date);
+        if !resting.is_empty() {
+            let mut config = self.clone();
+            config.teams_left = self.teams_left.difference(&resting);
+            return vec![config];
+        }
+
+        // Every team left has to play today, sit out because a teammate in its rest group did, or
+        // sit out a spare day, so branching on the games of one team and its rest mates, and on it
+        // sitting out, covers every way to fill the day. The team with the fewest games goes
+        // first, so dead ends turn up early. Days stay in date order since rest, rematches and
+        // weekend balance depend on earlier days.
+        let candidates = self
+            .day_games
+            .iter()
+            .copied()
+            .filter(|gi| self.remaining[*gi] > 0 && self.is_open(*gi));
+        let mut counts = [0; TeamSet::MAX_TEAMS];
+        for gi in candidates.clone() {
+            let game = &self.games[gi];
+            let teams = self.rest_mates[game.ti0.0].union(&self.rest_mates[game.ti1.0]);
+            for id in teams.intersection(&self.teams_left).iter() {
+                counts[id.0] += 1;
             }
         }
+        let team = match self.teams_left.iter().min_by_key(|id| counts[id.0]) {
+            Some(team) => team,
+            None => return vec![],
+        };
+        let mates = self.rest_mates[team.0];
+
+        let mut successors = Vec::new();
+        // Before midseason, try first meetings before repeats so repeats land in the second half.
+        let mut rematches = Vec::new();
+        let first_half = date < self.midseason();
+
+        for gi in candidates {
+            let game = &self.games[gi];
+            if !mates.contains(game.ti0) && !mates.contains(game.ti1) {
+                continue;
+            }
+            let successor = self.with_game(gi);
+            if first_half && self.last_meeting(game).is_some() {
+                rematches.push(successor);
+            } else {
+                successors.push(successor);
+            }
+        }
+        successors.extend(rematches);
+        if self.can_sit_out(team) {
+            let mut config = self.clone();
+            config.teams_left.remove(team);
+            successors.push(config);
+        }
         successors
     }
 
     fn is_valid(&self) -> bool {
-        true
+        self.is_valid_for(true)
+    }
+    fn is_goal(&self) -> bool {
+        self.is_out_of_days() && self.num_remaining == 0
+    }
+}
+
+// (weekend games, weekday games) per team.
+pub fn get_weekend_games(days: &[Day], num_teams: usize) -> Vec<(usize, usize)> {
+    let mut games = vec![(0, 0); num_teams];
+    for day in days.iter() {
+        for g in day.games.iter() {
+            for id in [g.ti0, g.ti1] {
+                if day.is_weekend() {
+                    games[id.0].0 += 1;
+                } else {
+                    games[id.0].1 += 1;
+                }
+            }
+        }
+    }
+    games
+}
+
+pub enum ScheduleOutcome {
+    Solved(ScheduleConfig),
+    // There were more games than slots on the days, and every day could be filled with these
+    // games left over.
+    OutOfDays(ScheduleConfig),
+    NoSolution,
+}
+
+// Searches until every day is filled, ignoring leftover games.
+#[derive(Clone, Debug)]
+struct FillDays(ScheduleConfig);
+
+impl Config for FillDays {
+    fn successors(&self) -> Vec<FillDays> {
+        self.0.successors().into_iter().map(FillDays).collect()
+    }
+
+    fn is_valid(&self) -> bool {
+        self.0.is_valid_for(false)
     }
+
     fn is_goal(&self) -> bool {
-        // self.days_index == self.days.len()
-        self.days_index == 15
+        self.0.is_out_of_days()
+    }
+}
+
+pub fn solve_schedule(config: ScheduleConfig) -> ScheduleOutcome {
+    let slots: usize = config.days.iter().map(|d| d.teams_playing.len() / 2).sum();
+    // Only fill days when there are fewer slots than games, since the full search can't succeed
+    // then. Otherwise a failed full search would be repeated almost in full.
+    if slots >= config.num_remaining {
+        return match backtrack::solve(config) {
+            Some(solution) => ScheduleOutcome::Solved(solution),
+            None => ScheduleOutcome::NoSolution,
+        };
+    }
+    match backtrack::solve(FillDays(config)) {
+        Some(FillDays(partial)) => ScheduleOutcome::OutOfDays(partial),
+        None => ScheduleOutcome::NoSolution,
     }
 }
 
-// mod test {
+#[cfg(test)]
+mod tests {
+    use crate::interleague_schedule::*;
+    use crate::sample_data::get_state_from_league_info;
 
-//     #[test]
-//     fn is_valid() {
-//         use super::*;
-//         let config = ScheduleConfig::new(5);
+    fn teams(ids: impl IntoIterator<Item = usize>) -> TeamSet {
+        ids.into_iter().map(TeamId).collect()
+    }
 
-//         assert!(config.is_valid(), "starting config is valid");
-//     }
-// }
+    fn game(ti0: usize, ti1: usize) -> Game {
+        Game {
+            ti0: TeamId(ti0),
+            ti1: TeamId(ti1),
+            distance: 0,
+            home: None,
+        }
+    }
+
+    fn get_config(fields: usize, homes: [(usize, usize, usize); 2]) -> ScheduleConfig {
+        let mut state =
+            get_state_from_league_info(&[("A".to_string(), 2), ("B".to_string(), 2)], 1);
+        state.leagues[0].venues.push(Venue {
+            name: "Park".to_string(),
+            fields,
+            fields_on: Default::default(),
+        });
+        let day = Day::new(NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(), teams(0..4));
+        let games = homes
+            .iter()
+            .map(|(ti0, ti1, home)| Game {
+                home: Some(TeamId(*home)),
+                ..game(*ti0, *ti1)
+            })
+            .collect();
+        ScheduleConfig::new(vec![day], games, &state).unwrap()
+    }
+
+    #[test]
+    fn goal() {
+        let state = get_state_from_league_info(&[("A".to_string(), 2)], 2);
+        let days: Vec<Day> = [23, 27]
+            .iter()
+            .map(|day| {
+                let date = NaiveDate::from_ymd_opt(2023, 3, *day).unwrap();
+                Day::new(date, teams(0..2))
+            })
+            .collect();
+        let config = ScheduleConfig::new(days.clone(), vec![game(0, 1); 2], &state).unwrap();
+        match solve_schedule(config) {
+            ScheduleOutcome::Solved(solution) => {
+                assert!(solution.days().iter().all(|d| d.games.len() == 1))
+            }
+            _ => panic!("expected a solution"),
+        }
+
+        let config = ScheduleConfig::new(days, vec![game(0, 1); 3], &state).unwrap();
+        match solve_schedule(config) {
+            ScheduleOutcome::OutOfDays(partial) => assert_eq!(partial.remaining_games().len(), 1),
+            _ => panic!("expected to run out of days"),
+        }
+    }
+
+    #[test]
+    fn pruning() {
+        let state = get_state_from_league_info(&[("A".to_string(), 4)], 2);
+        let days: Vec<Day> = [23, 27]
+            .iter()
+            .map(|day| {
+                let date = NaiveDate::from_ymd_opt(2023, 3, *day).unwrap();
+                Day::new(date, teams(0..4))
+            })
+            .collect();
+
+        // Two meetings of the same teams are a single branch.
+        let games = vec![game(0, 1), game(2, 3), game(0, 1), game(2, 3)];
+        let config = ScheduleConfig::new(days.clone(), games, &state).unwrap();
+        assert_eq!(config.games.len(), 2);
+        assert_eq!(config.remaining, vec![2, 2]);
+        let successors = config.successors();
+        assert_eq!(successors.len(), 1);
+        assert_eq!(successors[0].remaining, vec![1, 2]);
+        assert_eq!(successors[0].remaining_games().len(), 3);
+
+        // 0 and 1 have three games for two days.
+        let games = vec![game(0, 1), game(0, 1), game(0, 2), game(1, 3), game(2, 3)];
+        let config = ScheduleConfig::new(days, games, &state).unwrap();
+        assert!(!config.is_valid());
+        assert!(FillDays(config).is_valid());
+    }
+
+    #[test]
+    fn rest() {
+        let mut state = get_state_from_league_info(&[("A".to_string(), 4)], 2);
+        state.rest.min_days = 1;
+        let days: Vec<Day> = [23, 24, 27]
+            .iter()
+            .map(|day| {
+                let date = NaiveDate::from_ymd_opt(2023, 3, *day).unwrap();
+                Day::new(date, teams(0..4))
+            })
+            .collect();
+        let games = vec![game(0, 1), game(2, 3), game(0, 2), game(1, 3)];
+
+        // Everyone sits out 3/24 after playing 3/23.
+        let config = ScheduleConfig::new(days.clone(), games.clone(), &state).unwrap();
+        match solve_schedule(config) {
+            ScheduleOutcome::Solved(solution) => {
+                let played: Vec<usize> = solution.days().iter().map(|d| d.games.len()).collect();
+                assert_eq!(played, vec![2, 0, 2]);
+            }
+            _ => panic!("expected a solution"),
+        }
+
+        // 0 and 2 share a pitcher, so they can't play the same day unless it's against each other.
+        // 1 has the fewest games on 3/23 and goes first.
+        state.rest.min_days = 0;
+        state.rest.groups.push(RestGroup {
+            teams: vec![TeamId(0), TeamId(2)],
+            min_days: 0,
+        });
+        let days: Vec<Day> = [(23, vec![0, 1, 2, 3]), (24, vec![0, 1]), (27, vec![2, 3])]
+            .into_iter()
+            .map(|(day, playing)| {
+                let date = NaiveDate::from_ymd_opt(2023, 3, day).unwrap();
+                Day::new(date, teams(playing))
+            })
+            .collect();
+        let config = ScheduleConfig::new(days, games, &state).unwrap();
+        let firsts: Vec<(usize, usize)> = config
+            .successors()
+            .iter()
+            .filter(|s| s.is_valid())
+            .map(|s| {
+                let g = s.days()[0].games[0];
+                (g.ti0.0, g.ti1.0)
+            })
+            .collect();
+        assert_eq!(firsts, vec![(1, 3)]);
+    }
+
+    #[test]
+    fn spare_days() {
+        // Two games each over three Saturdays, so every team sits one out.
+        let mut state =
+            get_state_from_league_info(&[("A".to_string(), 2), ("B".to_string(), 2)], 2);
+        state.rest.min_days = 1;
+        let days: Vec<Day> = [(3, 23), (3, 30), (4, 6)]
+            .iter()
+            .map(|(month, day)| {
+                let date = NaiveDate::from_ymd_opt(2024, *month, *day).unwrap();
+                Day::new(date, teams(0..4))
+            })
+            .collect();
+        let games = vec![game(0, 2), game(0, 3), game(1, 2), game(1, 3)];
+        let config = ScheduleConfig::new(days.clone(), games.clone(), &state).unwrap();
+        match solve_schedule(config) {
+            ScheduleOutcome::Solved(solution) => {
+                assert_eq!(solution.remaining_games().len(), 0);
+                let played: usize = solution.days().iter().map(|d| d.games.len()).sum();
+                assert_eq!(played, 4);
+            }
+            _ => panic!("expected a solution"),
+        }
+
+        // Without rest rules every day has to be filled.
+        state.rest.min_days = 0;
+        assert!(matches!(
+            ScheduleConfig::new(days, games, &state),
+            Err(ScheduleError::DaysForTarget {
+                days: 3,
+                target_games: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn weekend_balance() {
+        let mut state = get_state_from_league_info(&[("A".to_string(), 4)], 1);
+        // Saturday 3/25 and Tuesday 3/28.
+        let days = vec![
+            Day::new(
+                NaiveDate::from_ymd_opt(2023, 3, 25).unwrap(),
+                teams(vec![0, 1]),
+            ),
+            Day::new(
+                NaiveDate::from_ymd_opt(2023, 3, 28).unwrap(),
+                teams(vec![2, 3]),
+            ),
+        ];
+        let games = vec![game(0, 1), game(2, 3)];
+
+        state.weekend_tolerance = Some(0);
+        let config = ScheduleConfig::new(days.clone(), games.clone(), &state).unwrap();
+        assert!(matches!(
+            solve_schedule(config),
+            ScheduleOutcome::NoSolution
+        ));
+
+        state.weekend_tolerance = Some(1);
+        let config = ScheduleConfig::new(days, games, &state).unwrap();
+        match solve_schedule(config) {
+            ScheduleOutcome::Solved(solution) => assert_eq!(
+                get_weekend_games(&solution.days(), 4),
+                vec![(1, 0), (1, 0), (0, 1), (0, 1)]
+            ),
+            _ => panic!("expected a solution"),
+        }
+
+        // Teams 2 and 3 play half as many games, so one weekend game each matches the two that
+        // teams 0 and 1 play.
+        state.set_target_games(4);
+        state.teams[2].target_games = 2;
+        state.teams[3].target_games = 2;
+        let days = [
+            (25, vec![0, 1, 2, 3]),
+            (28, vec![0, 1]),
+            (1, vec![0, 1]),
+            (4, vec![0, 1, 2, 3]),
+        ]
+        .into_iter()
+        .map(|(day, playing)| {
+            let month = if day > 20 { 3 } else { 4 };
+            Day::new(
+                NaiveDate::from_ymd_opt(2023, month, day).unwrap(),
+                teams(playing),
+            )
+        })
+        .collect();
+        let games = vec![
+            game(0, 1),
+            game(0, 1),
+            game(0, 2),
+            game(1, 3),
+            game(0, 3),
+            game(1, 2),
+        ];
+        state.weekend_tolerance = Some(0);
+        let config = ScheduleConfig::new(days, games, &state).unwrap();
+        match solve_schedule(config) {
+            ScheduleOutcome::Solved(solution) => assert_eq!(
+                get_weekend_games(&solution.days(), 4),
+                vec![(2, 2), (2, 2), (1, 1), (1, 1)]
+            ),
+            _ => panic!("expected a solution"),
+        }
+    }
+
+    #[test]
+    fn rematches() {
+        let mut state = get_state_from_league_info(&[("A".to_string(), 4)], 4);
+        state.rematch_min_days = 6;
+        let days: Vec<Day> = [1, 4, 8, 11]
+            .iter()
+            .map(|day| {
+                let date = NaiveDate::from_ymd_opt(2023, 4, *day).unwrap();
+                Day::new(date, teams(0..4))
+            })
+            .collect();
+        let games = vec![
+            game(0, 1),
+            game(0, 1),
+            game(2, 3),
+            game(2, 3),
+            game(0, 2),
+            game(1, 3),
+            game(0, 3),
+            game(1, 2),
+        ];
+        let config = ScheduleConfig::new(days, games, &state).unwrap();
+        match solve_schedule(config) {
+            ScheduleOutcome::Solved(solution) => {
+                let dates_01: Vec<u32> = solution
+                    .days()
+                    .iter()
+                    .filter(|d| {
+                        d.games
+                            .iter()
+                            .any(|g| g.ti0 == TeamId(0) && g.ti1 == TeamId(1))
+                    })
+                    .map(|d| d.date.day())
+                    .collect();
+                assert_eq!(dates_01.len(), 2);
+                assert!(dates_01[1] - dates_01[0] >= 7);
+            }
+            _ => panic!("expected a solution"),
+        }
+    }
+
+    #[test]
+    fn field_capacity() {
+        // A can host one game, B any number.
+        let config = get_config(1, [(0, 1, 0), (2, 3, 2)]);
+        let successors = config.successors();
+        assert_eq!(successors.len(), 1);
+        assert_eq!(successors[0].successors().len(), 1);
+
+        // Both games hosted by A.
+        let config = get_config(1, [(0, 2, 0), (1, 3, 1)]);
+        let successors = config.successors();
+        assert_eq!(successors.len(), 1);
+        assert!(successors[0].successors().is_empty());
+
+        let config = get_config(2, [(0, 2, 0), (1, 3, 1)]);
+        assert!(config
+            .successors()
+            .iter()
+            .all(|s| s.successors().len() == 1));
+
+        // Two games a day, with one field between both leagues.
+        let mut state =
+            get_state_from_league_info(&[("A".to_string(), 2), ("B".to_string(), 2)], 1);
+        for (league, fields) in state.leagues.iter_mut().zip([1, 0]) {
+            league.venues.push(Venue {
+                name: "Park".to_string(),
+                fields,
+                fields_on: Default::default(),
+            });
+        }
+        let day = Day::new(NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(), teams(0..4));
+        assert!(matches!(
+            ScheduleConfig::new(vec![day], vec![game(0, 2), game(1, 3)], &state),
+            Err(ScheduleError::NotEnoughFields { games: 2, fields: 1, leagues, .. })
+                if leagues == vec!["A", "B"]
+        ));
+
+        let day = Day::new(NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(), teams(2..6));
+        assert!(matches!(
+            ScheduleConfig::new(vec![day], vec![], &state),
+            Err(ScheduleError::UnknownTeamIndex { index: 4, .. })
+        ));
+
+        let state = get_state_from_league_info(&[("A".to_string(), 2), ("B".to_string(), 2)], 1);
+        let day = Day::new(NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(), teams(0..4));
+        let result = ScheduleConfig::new(vec![day.clone()], vec![game(0, 2), game(1, 7)], &state);
+        assert!(matches!(
+            result,
+            Err(ScheduleError::UnknownTeamIndex {
+                date: None,
+                index: 7
+            })
+        ));
+
+        let state = get_state_from_league_info(&[("A".to_string(), 2), ("B".to_string(), 128)], 1);
+        assert!(matches!(
+            ScheduleConfig::new(vec![day], vec![], &state),
+            Err(ScheduleError::TooManyTeams(130))
+        ));
+    }
+}
@@ -1,5 +1,17 @@
+pub mod calendar;
+pub mod diagnose;
+pub mod distances;
+pub mod equity;
+pub mod error;
+pub mod grid;
+pub mod home_away;
+pub mod ical;
 pub mod interleague_schedule;
+pub mod local_search;
+pub mod optimal;
 pub mod sample_data;
+pub mod season;
+pub mod team_set;
 pub mod teams;
 
 pub use backtrack::{solve, Config};
@@ -0,0 +1,381 @@
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::error::ScheduleError;
+use crate::interleague_schedule::*;
+use crate::team_set::TeamSet;
+use crate::teams::*;
+
+// Simulated annealing over which day each game is played, since the backtracking search stops at
+// the first schedule it finds. It starts from a solved or partial schedule and only makes moves
+// that keep the search's rules (availability, fields, rest, rematches, weekend tolerance and
+// every day filled), looking for a lower weighted cost:
+//
+// - travel: distance score of weekday games, so long trips land on weekends
+// - rest: 2 for a game the day after a team's previous one, 1 with one day off in between
+// - weekend: sum of squared differences between each team's weekend games and the average, with
+//   teams that have smaller targets scaled up as in the weekend tolerance
+// - spacing: days short of even spacing between repeat meetings of a pair, with the season
+//   split evenly between the meetings
+//
+// Games left over from a partial schedule cost far more than any of these, so placing them comes
+// first.
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Weights {
+    pub travel: f64,
+    pub rest: f64,
+    pub weekend: f64,
+    pub spacing: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Weights {
+        Weights {
+            travel: 1.0,
+            rest: 1.0,
+            weekend: 1.0,
+            spacing: 1.0,
+        }
+    }
+}
+
+const UNSCHEDULED_WEIGHT: f64 = 1000.0;
+const START_TEMPERATURE: f64 = 10.0;
+const END_TEMPERATURE: f64 = 0.01;
+const SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// Searching stops at whichever runs out first.
+#[derive(Copy, Clone, Debug)]
+pub struct Budget {
+    pub time: Duration,
+    pub iterations: usize,
+}
+
+pub struct Improvement {
+    pub config: ScheduleConfig,
+    pub cost_before: f64,
+    pub cost_after: f64,
+    pub iterations: usize,
+}
+
+// xorshift64*, enough to pick moves without pulling in a dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn pair(game: &Game) -> (TeamId, TeamId) {
+    (game.ti0.min(game.ti1), game.ti0.max(game.ti1))
+}
+
+fn playing(day: &Day) -> TeamSet {
+    day.games.iter().flat_map(|g| [g.ti0, g.ti1]).collect()
+}
+
+pub fn get_cost(days: &[Day], unscheduled: &[Game], state: &State, weights: &Weights) -> f64 {
+    let num_teams = state.teams.len();
+    let mut travel = 0;
+    let mut rest = 0;
+    let mut weekend_games = vec![0.0; num_teams];
+    let mut last_games: Vec<Option<NaiveDate>> = vec![None; num_teams];
+    let mut meetings: HashMap<(TeamId, TeamId), Vec<NaiveDate>> = HashMap::new();
+    for day in days.iter() {
+        for g in day.games.iter() {
+            if !day.is_weekend() {
+                travel += g.distance;
+            }
+            for id in [g.ti0, g.ti1] {
+                if let Some(last) = last_games[id.0] {
+                    rest += (3 - (day.date - last).num_days()).max(0);
+                }
+                last_games[id.0] = Some(day.date);
+                if day.is_weekend() {
+                    weekend_games[id.0] += 1.0;
+                }
+            }
+            meetings.entry(pair(g)).or_default().push(day.date);
+        }
+    }
+
+    let most_games = state
+        .teams
+        .iter()
+        .map(|t| t.target_games)
+        .max()
+        .unwrap_or(0);
+    for team in state.teams.iter().filter(|t| t.target_games > 0) {
+        weekend_games[team.id.0] *= most_games as f64 / team.target_games as f64;
+    }
+    let average = weekend_games.iter().sum::<f64>() / num_teams as f64;
+    let weekend: f64 = weekend_games.iter().map(|w| (w - average).powi(2)).sum();
+
+    let season_days = match (days.first(), days.last()) {
+        (Some(first), Some(last)) => (last.date - first.date).num_days() as f64,
+        _ => 0.0,
+    };
+    let mut spacing = 0.0;
+    for dates in meetings.values().filter(|dates| dates.len() > 1) {
+        let even = season_days / dates.len() as f64;
+        for w in dates.windows(2) {
+            spacing += (even - (w[1] - w[0]).num_days() as f64).max(0.0);
+        }
+    }
+
+    weights.travel * travel as f64
+        + weights.rest * rest as f64
+        + weights.weekend * weekend
+        + weights.spacing * spacing
+        + UNSCHEDULED_WEIGHT * unscheduled.len() as f64
+}
+
+// Whether days follow every rule the search does, with unscheduled games left over.
+pub fn is_feasible(days: &[Day], unscheduled: &[Game], state: &State) -> bool {
+    ScheduleConfig::from_days(days, unscheduled.to_vec(), state).is_ok()
+}
+
+// Swaps games between two days along the chain that starts with team's game on d0: team's game on
+// d0, its opponent's game on d1, that opponent's game on d0, and so on. Every team in the chain
+// plays both days before and after, so nothing changes about who plays when. Fails if the chain
+// reaches a team without a game on one of the days.
+fn swap_chain(days: &mut [Day], d0: usize, d1: usize, team: TeamId) -> bool {
+    let mut chain: [Vec<usize>; 2] = [vec![], vec![]];
+    let mut id = team;
+    let mut side = 0;
+    loop {
+        let d = [d0, d1][side];
+        let gi = match days[d]
+            .games
+            .iter()
+            .position(|g| g.ti0 == id || g.ti1 == id)
+        {
+            Some(gi) => gi,
+            None => return false,
+        };
+        let g = days[d].games[gi];
+        chain[side].push(gi);
+        id = if g.ti0 == id { g.ti1 } else { g.ti0 };
+        side = 1 - side;
+        if side == 0 && id == team {
+            break;
+        }
+    }
+    let mut moved: [Vec<Game>; 2] = [vec![], vec![]];
+    for side in 0..2 {
+        let d = [d0, d1][side];
+        chain[side].sort_by(|a, b| b.cmp(a));
+        for gi in chain[side].iter() {
+            moved[side].push(days[d].games.remove(*gi));
+        }
+    }
+    let [moved0, moved1] = moved;
+    days[d0].games.extend(moved1);
+    days[d1].games.extend(moved0);
+    true
+}
+
+// Whether both of a game's teams are available and not yet playing on day.
+fn is_free(day: &Day, game: &Game) -> bool {
+    let playing = playing(day);
+    [game.ti0, game.ti1]
+        .iter()
+        .all(|id| day.teams_playing.contains(*id) && !playing.contains(*id))
+}
+
+// Makes a random change, returning false if the one picked isn't possible.
+fn make_move(days: &mut [Day], unscheduled: &mut Vec<Game>, rng: &mut Rng) -> bool {
+    if days.is_empty() {
+        return false;
+    }
+    let moves = if unscheduled.is_empty() { 2 } else { 4 };
+    let d0 = rng.below(days.len());
+    let d1 = rng.below(days.len());
+    match rng.below(moves) {
+        // Swap a chain of games between two days.
+        0 => {
+            if d0 == d1 || days[d0].games.is_empty() {
+                return false;
+            }
+            let g = days[d0].games[rng.below(days[d0].games.len())];
+            swap_chain(days, d0, d1, g.ti0)
+        }
+        // Move a game to another day.
+        1 => {
+            if d0 == d1 || days[d0].games.is_empty() {
+                return false;
+            }
+            let gi = rng.below(days[d0].games.len());
+            if !is_free(&days[d1], &days[d0].games[gi]) {
+                return false;
+            }
+            let g = days[d0].games.remove(gi);
+            days[d1].games.push(g);
+            true
+        }
+        // Place a leftover game.
+        2 => {
+            let ui = rng.below(unscheduled.len());
+            if !is_free(&days[d0], &unscheduled[ui]) {
+                return false;
+            }
+            days[d0].games.push(unscheduled.remove(ui));
+            true
+        }
+        // Trade a day's a - x and c - y for leftover a - c and x - y, which changes which games
+        // are left over without changing who plays when.
+        _ => {
+            let ui = rng.below(unscheduled.len());
+            let u = unscheduled[ui];
+            let day = &days[d0];
+            let find = |id: TeamId| day.games.iter().position(|g| g.ti0 == id || g.ti1 == id);
+            let (gi0, gi1) = match (find(u.ti0), find(u.ti1)) {
+                (Some(gi0), Some(gi1)) if gi0 != gi1 => (gi0, gi1),
+                _ => return false,
+            };
+            let other = |gi: usize, id: TeamId| {
+                let g = &day.games[gi];
+                if g.ti0 == id {
+                    g.ti1
+                } else {
+                    g.ti0
+                }
+            };
+            let xy = pair(&Game {
+                ti0: other(gi0, u.ti0),
+                ti1: other(gi1, u.ti1),
+                distance: 0,
+                home: None,
+            });
+            let uj = match unscheduled
+                .iter()
+                .enumerate()
+                .position(|(uj, g)| uj != ui && pair(g) == xy)
+            {
+                Some(uj) => uj,
+                None => return false,
+            };
+            let traded: Vec<Game> = [gi0.max(gi1), gi0.min(gi1)]
+                .iter()
+                .map(|gi| days[d0].games.remove(*gi))
+                .collect();
+            for uk in [ui.max(uj), ui.min(uj)] {
+                let w = unscheduled.remove(uk);
+                days[d0].games.push(w);
+            }
+            unscheduled.extend(traded);
+            true
+        }
+    }
+}
+
+// Anneals from config's schedule, returning the best one found as a finished config.
+pub fn improve_schedule(
+    config: &ScheduleConfig,
+    state: &State,
+    weights: &Weights,
+    budget: &Budget,
+) -> Result<Improvement, ScheduleError> {
+    let mut days = config.scheduled_days();
+    let mut unscheduled = config.remaining_games();
+    let mut cost = get_cost(&days, &unscheduled, state, weights);
+    let cost_before = cost;
+    let mut best = (
+        ScheduleConfig::from_days(&days, unscheduled.clone(), state)?,
+        cost,
+    );
+    let mut rng = Rng(SEED);
+    let start = Instant::now();
+    let mut iterations = 0;
+    while iterations < budget.iterations && start.elapsed() < budget.time {
+        iterations += 1;
+        let progress = (iterations as f64 / budget.iterations as f64)
+            .max(start.elapsed().as_secs_f64() / budget.time.as_secs_f64());
+        let temperature = START_TEMPERATURE * (END_TEMPERATURE / START_TEMPERATURE).powf(progress);
+
+        let mut new_days = days.clone();
+        let mut new_unscheduled = unscheduled.clone();
+        if !make_move(&mut new_days, &mut new_unscheduled, &mut rng) {
+            continue;
+        }
+        let new_config = match ScheduleConfig::from_days(&new_days, new_unscheduled.clone(), state)
+        {
+            Ok(new_config) => new_config,
+            Err(_) => continue,
+        };
+        let new_cost = get_cost(&new_days, &new_unscheduled, state, weights);
+        if new_cost <= cost || rng.unit() < ((cost - new_cost) / temperature).exp() {
+            days = new_days;
+            unscheduled = new_unscheduled;
+            cost = new_cost;
+            if cost < best.1 {
+                best = (new_config, cost);
+            }
+        }
+    }
+    let (config, cost_after) = best;
+    Ok(Improvement {
+        config,
+        cost_before,
+        cost_after,
+        iterations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::local_search::*;
+    use crate::sample_data::*;
+    use crate::Config;
+
+    #[test]
+    fn improve() {
+        let (state, config) = get_2023_aaa_filled_config().unwrap();
+        let solution = match solve_schedule(config) {
+            ScheduleOutcome::Solved(solution) => solution,
+            _ => panic!("expected a solution"),
+        };
+        assert!(is_feasible(&solution.scheduled_days(), &[], &state));
+
+        let weights = Weights::default();
+        let budget = Budget {
+            time: Duration::from_secs(60),
+            iterations: 2000,
+        };
+        let improvement = improve_schedule(&solution, &state, &weights, &budget).unwrap();
+        assert_eq!(improvement.iterations, 2000);
+        assert!(improvement.cost_after < improvement.cost_before);
+        let days = improvement.config.scheduled_days();
+        assert!(is_feasible(&days, &[], &state));
+        assert!(improvement.config.is_goal());
+        assert_eq!(
+            get_cost(&days, &[], &state, &weights),
+            improvement.cost_after
+        );
+
+        // A game moved onto a day both teams already play.
+        let mut broken = days.clone();
+        let game = broken[0].games.remove(0);
+        broken[1].games.push(game);
+        assert!(!is_feasible(&broken, &[], &state));
+    }
+
+    #[test]
+    fn no_days() {
+        assert!(!make_move(&mut [], &mut vec![], &mut Rng(SEED)));
+    }
+}
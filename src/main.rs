@@ -1,55 +1,536 @@
+use ::interleague_schedule::diagnose::*;
+use ::interleague_schedule::equity::*;
+use ::interleague_schedule::error::ScheduleError;
+use ::interleague_schedule::grid::*;
+use ::interleague_schedule::home_away::*;
+use ::interleague_schedule::ical::write_calendars;
 use ::interleague_schedule::interleague_schedule::*;
-use ::interleague_schedule::sample_data::*;
+use ::interleague_schedule::local_search::*;
+use ::interleague_schedule::optimal::get_optimal_teams_to_play_against;
+use ::interleague_schedule::season::*;
 use ::interleague_schedule::teams::*;
 
-pub mod interleague_schedule;
-
-// fn main() {
-//     let mut state = get_2023_aaa_softball_state();
-//     let league_distance_matrix = get_district8_city_league_distances();
-//     let team_matrix = get_teams_to_play_against(&mut state, &league_distance_matrix, 14);
-//     let mut remaining_games: Vec<Game> = Vec::new();
-//     for ti0 in 0..state.teams.len() {
-//         for ti1 in ti0 + 1..state.teams.len() {
-//             for _ in 0..get_matrix_val(&team_matrix, ti0, ti1) {
-//                 let distance = get_matrix_val(
-//                     &league_distance_matrix,
-//                     state.teams[ti0].league_index,
-//                     state.teams[ti1].league_index,
-//                 );
-//                 remaining_games.push(Game { ti0, ti1, distance });
-//             }
-//         }
-//     }
-//     remaining_games.sort_by_key(|g| g.distance);
-
-//     let mut days = get_2023_aaa_softball_days(state.teams.len());
-//     days.sort_by_key(|k| k.teams_playing.len());
-
-//     let schedule_config = ScheduleConfig {
-//         days,
-//         days_index: 0,
-//         remaining_games,
-//     };
-//     println!("{:?}", schedule_config);
-
-//     println!(
-//         "{}",
-//         match backtrack::solve(schedule_config) {
-//             Some(solution) => format!(
-//                 "{:?}remaining_games: {:?}",
-//                 solution, solution.remaining_games
-//             ),
-//             None => "No solution found".to_string(),
-//         }
-//     );
-// }
-
-fn main() {
-    let mut state = get_2024_aaa_softball_state();
-    let league_distance_matrix = get_district8_city_league_distances();
-    let team_matrix = get_teams_to_play_against(&mut state, &league_distance_matrix, 14);
-    dump_matrix(team_matrix);
-    println!("{:?}", state);
-    dump_travel_scores(&state, &league_distance_matrix);
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde_json::json;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(about = "Generate an interleague master schedule")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Pick how many times each pair of teams plays.
+    Matchups(StageArgs),
+    /// Pick matchups, then assign them to days.
+    Schedule(ScheduleArgs),
+    /// Pick matchups, then report each team's travel score.
+    Report(StageArgs),
+    /// Convert a season file between TOML and JSON.
+    Convert(ConvertArgs),
+    /// Convert a published schedule grid CSV into a game list CSV.
+    Grid(GridArgs),
+}
+
+#[derive(Args)]
+struct StageArgs {
+    /// Season file (TOML, or JSON with a .json extension).
+    season: PathBuf,
+    /// Output file. Writes to stdout if not given.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Pick interleague games with an exact optimizer for total travel instead of greedily.
+    #[arg(long)]
+    optimal: bool,
+    /// Most times two teams from different leagues can meet, with --optimal.
+    #[arg(long, default_value_t = 1, requires = "optimal")]
+    max_meetings: i32,
+    /// What to keep low once the games are picked: total travel, or how it's spread out.
+    #[arg(long, value_enum, default_value_t = Objective::Total)]
+    objective: Objective,
+}
+
+#[derive(Args)]
+struct ScheduleArgs {
+    #[command(flatten)]
+    stage: StageArgs,
+    /// Also write an iCalendar file per team and per league into this directory.
+    #[arg(long)]
+    ics_dir: Option<PathBuf>,
+    /// Then spend this many seconds improving which day each game is played.
+    #[arg(long, value_name = "SECONDS")]
+    improve: Option<u64>,
+    /// Weight of weekday travel, with --improve.
+    #[arg(long, default_value_t = 1.0, requires = "improve")]
+    travel_weight: f64,
+    /// Weight of games with little rest before them, with --improve.
+    #[arg(long, default_value_t = 1.0, requires = "improve")]
+    rest_weight: f64,
+    /// Weight of uneven weekend games, with --improve.
+    #[arg(long, default_value_t = 1.0, requires = "improve")]
+    weekend_weight: f64,
+    /// Weight of repeat meetings closer than evenly spaced, with --improve.
+    #[arg(long, default_value_t = 1.0, requires = "improve")]
+    spacing_weight: f64,
+}
+
+impl ScheduleArgs {
+    fn weights(&self) -> Weights {
+        Weights {
+            travel: self.travel_weight,
+            rest: self.rest_weight,
+            weekend: self.weekend_weight,
+            spacing: self.spacing_weight,
+        }
+    }
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    /// Season file (TOML, or JSON with a .json extension).
+    season: PathBuf,
+    /// Output file. Writes to stdout if not given.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    #[arg(short, long, value_enum, default_value_t = SeasonFormat::Json)]
+    format: SeasonFormat,
+}
+
+#[derive(Args)]
+struct GridArgs {
+    /// Season file with the leagues named in the grid.
+    season: PathBuf,
+    /// Grid CSV: away teams across the header row, home team or "bye" in each cell.
+    grid: PathBuf,
+    /// Output file. Writes to stdout if not given.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Objective {
+    /// Total travel score.
+    Total,
+    /// Highest team travel score.
+    MaxTeam,
+    /// Highest league average travel score.
+    MaxLeague,
+    /// Variance of team travel scores.
+    Variance,
+}
+
+impl Objective {
+    fn travel_objective(self) -> TravelObjective {
+        match self {
+            Objective::Total => TravelObjective::Total,
+            Objective::MaxTeam => TravelObjective::MaxTeam,
+            Objective::MaxLeague => TravelObjective::MaxLeague,
+            Objective::Variance => TravelObjective::Variance,
+        }
+    }
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum SeasonFormat {
+    Toml,
+    Json,
+}
+
+fn open_output(output: &Option<PathBuf>) -> io::Result<Box<dyn Write>> {
+    Ok(match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    })
+}
+
+// Matchups for the lowest total travel score.
+fn get_total_travel_matchups(
+    season: &Season,
+    args: &StageArgs,
+) -> Result<(State, Matrix, Matrix), ScheduleError> {
+    let mut state = season.state();
+    let league_distance_matrix = season.league_distance_matrix()?;
+    let team_matrix = if args.optimal {
+        get_optimal_teams_to_play_against(&mut state, &league_distance_matrix, args.max_meetings)?
+    } else {
+        get_teams_to_play_against(&mut state, &league_distance_matrix)?
+    };
+    Ok((state, league_distance_matrix, team_matrix))
+}
+
+fn get_matchups(
+    season: &Season,
+    args: &StageArgs,
+) -> Result<(State, Matrix, Matrix), ScheduleError> {
+    let (mut state, league_distance_matrix, mut team_matrix) =
+        get_total_travel_matchups(season, args)?;
+    if args.objective != Objective::Total {
+        balance_travel(
+            &mut state,
+            &mut team_matrix,
+            &league_distance_matrix,
+            args.objective.travel_objective(),
+        );
+    }
+    Ok((state, league_distance_matrix, team_matrix))
+}
+
+// How the travel is spread out, compared with the greedy or optimal matchups before balancing.
+fn format_objective_comparison(
+    season: &Season,
+    args: &StageArgs,
+    state: &State,
+    league_distance_matrix: &Matrix,
+) -> Result<String, ScheduleError> {
+    let (total_state, _, _) = get_total_travel_matchups(season, args)?;
+    let summary = |state: &State| -> String {
+        let travel_scores = get_travel_scores(state, league_distance_matrix);
+        let total: i32 = travel_scores.iter().sum();
+        let mean = total as f64 / travel_scores.len() as f64;
+        let variance = travel_scores
+            .iter()
+            .map(|t| (*t as f64 - mean).powi(2))
+            .sum::<f64>()
+            / travel_scores.len() as f64;
+        let max_league = get_league_travel_scores(state, &travel_scores)
+            .into_iter()
+            .fold(0.0, f64::max);
+        format!(
+            "total {}, max team {}, max league average {:.1}, std dev {:.1}",
+            total,
+            travel_scores.iter().max().unwrap_or(&0),
+            max_league,
+            variance.sqrt()
+        )
+    };
+    let baseline = if args.optimal { "Optimal" } else { "Greedy" };
+    Ok(format!(
+        "{}: {}\nBalanced travel: {}\n",
+        baseline,
+        summary(&total_state),
+        summary(state)
+    ))
+}
+
+// How the greedy matchups compare with the optimal ones, before any travel balancing.
+fn format_optimality_gap(
+    season: &Season,
+    optimal: &State,
+    league_distance_matrix: &Matrix,
+) -> String {
+    let mut greedy = season.state();
+    // Greedy games short of their targets are part of the comparison, not an error.
+    let _ = get_teams_to_play_against(&mut greedy, league_distance_matrix);
+    let total_travel = |state: &State| -> i32 {
+        get_travel_scores(state, league_distance_matrix)
+            .iter()
+            .sum()
+    };
+    format!(
+        "Greedy: total travel score {}, {} games short of targets\n\
+         Optimal: total travel score {}, {} games short of targets\n",
+        total_travel(&greedy),
+        get_games_short(&greedy),
+        total_travel(optimal),
+        get_games_short(optimal)
+    )
+}
+
+// (home, away, games) for every pair with games, from a full home matrix.
+fn get_home_away_list(home_matrix: &Matrix) -> Vec<(TeamId, TeamId, i32)> {
+    let mut list = Vec::new();
+    for (home, row) in home_matrix.iter().enumerate() {
+        for (away, games) in row.iter().enumerate() {
+            if *games > 0 {
+                list.push((TeamId(home), TeamId(away), *games));
+            }
+        }
+    }
+    list
+}
+
+fn format_weekend_games(state: &State, days: &[Day]) -> String {
+    let mut s = String::from("Weekend / weekday games:\n");
+    for (team, (weekend, weekday)) in state
+        .teams
+        .iter()
+        .zip(get_weekend_games(days, state.teams.len()))
+    {
+        s.push_str(&format!(" - {}: {} / {}\n", team.name, weekend, weekday));
+    }
+    s
+}
+
+fn matchups(args: &StageArgs) -> Result<(), Box<dyn Error>> {
+    let season = Season::from_path(&args.season)?;
+    let (state, _, team_matrix) = get_matchups(&season, args)?;
+    let home_matrix = get_home_games(&team_matrix);
+    let mut out = open_output(&args.output)?;
+
+    match args.format {
+        Format::Text => {
+            for row in team_matrix.iter() {
+                writeln!(out, "{:?}", row)?;
+            }
+            write!(out, "{:?}", state)?;
+            writeln!(out, "Home games:")?;
+            for row in home_matrix.iter() {
+                writeln!(out, "{:?}", row)?;
+            }
+        }
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["Home", "Away", "Games"])?;
+            for (home, away, games) in get_home_away_list(&home_matrix) {
+                writer.write_record([
+                    state.team_name(home),
+                    state.team_name(away),
+                    &games.to_string(),
+                ])?;
+            }
+            writer.flush()?;
+        }
+        Format::Json => {
+            let matchups: Vec<_> = get_home_away_list(&home_matrix)
+                .into_iter()
+                .map(|(home, away, games)| {
+                    json!({
+                        "home": state.team_name(home),
+                        "away": state.team_name(away),
+                        "games": games,
+                    })
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut out, &matchups)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+fn format_out_of_days(state: &State, partial: &ScheduleConfig) -> String {
+    let games: Vec<String> = partial
+        .remaining_games()
+        .iter()
+        .map(|g| format!("{} - {}", state.team_name(g.ti0), state.team_name(g.ti1)))
+        .collect();
+    format!(
+        "Ran out of days with {} games left: {}",
+        games.len(),
+        games.join(", ")
+    )
+}
+
+fn schedule(schedule_args: &ScheduleArgs) -> Result<(), Box<dyn Error>> {
+    let args = &schedule_args.stage;
+    let season = Season::from_path(&args.season)?;
+    let (state, league_distance_matrix, team_matrix) = get_matchups(&season, args)?;
+    let mut remaining_games = get_remaining_games(&state, &team_matrix, &league_distance_matrix);
+    let home_matrix = get_home_games(&team_matrix);
+    if state.leagues.iter().any(|l| !l.venues.is_empty()) {
+        // Field limits depend on the host league, so pick home teams before scheduling.
+        assign_home_games(&mut remaining_games, &home_matrix);
+    }
+    let days = season.days();
+    let diagnosis = diagnose_days(&days, &remaining_games, &state);
+    let schedule_config = match ScheduleConfig::new(days, remaining_games, &state) {
+        Ok(schedule_config) => schedule_config,
+        Err(e) => {
+            eprint!("{}", diagnosis.format(&state));
+            return Err(e.into());
+        }
+    };
+
+    let mut solution = match solve_schedule(schedule_config) {
+        ScheduleOutcome::Solved(solution) => solution,
+        // Local search may still place the leftover games.
+        ScheduleOutcome::OutOfDays(partial) if schedule_args.improve.is_some() => partial,
+        ScheduleOutcome::OutOfDays(partial) => {
+            eprint!("{}", diagnosis.format(&state));
+            return Err(format_out_of_days(&state, &partial).into());
+        }
+        ScheduleOutcome::NoSolution => {
+            eprint!("{}", diagnosis.format(&state));
+            return Err("No solution found".into());
+        }
+    };
+    if let Some(seconds) = schedule_args.improve {
+        let budget = Budget {
+            time: Duration::from_secs(seconds),
+            iterations: usize::MAX,
+        };
+        let improvement = improve_schedule(&solution, &state, &schedule_args.weights(), &budget)?;
+        eprintln!(
+            "Local search: cost {:.1} -> {:.1} in {} iterations",
+            improvement.cost_before, improvement.cost_after, improvement.iterations
+        );
+        solution = improvement.config;
+        if !solution.remaining_games().is_empty() {
+            eprint!("{}", diagnosis.format(&state));
+            return Err(format_out_of_days(&state, &solution).into());
+        }
+    }
+    let mut days = solution.days();
+    assign_home_teams(&mut days, &home_matrix);
+    if let Some(ics_dir) = &schedule_args.ics_dir {
+        write_calendars(ics_dir, &days, &state, chrono::Utc::now().naive_utc())?;
+    }
+    let mut out = open_output(&args.output)?;
+
+    match args.format {
+        Format::Text => {
+            write!(out, "{:?}", solution)?;
+            writeln!(out, "remaining_games: {:?}", solution.remaining_games())?;
+            write!(out, "{}", format_weekend_games(&state, &days))?;
+        }
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["Date", "Day", "Home", "Away", "Host League"])?;
+            for day in days.iter() {
+                for g in day.games.iter() {
+                    let (home, away) = g.home_away();
+                    writer.write_record([
+                        day.date.to_string(),
+                        day.date.format("%a").to_string(),
+                        state.team_name(home).to_string(),
+                        state.team_name(away).to_string(),
+                        state.leagues[state.teams[home].league].name.clone(),
+                    ])?;
+                }
+            }
+            writer.flush()?;
+            eprint!("{}", format_weekend_games(&state, &days));
+        }
+        Format::Json => {
+            let json_days: Vec<_> = days
+                .iter()
+                .map(|day| {
+                    let games: Vec<_> = day
+                        .games
+                        .iter()
+                        .map(|g| {
+                            let (home, away) = g.home_away();
+                            json!({
+                                "home": state.team_name(home),
+                                "away": state.team_name(away),
+                                "host_league": state.leagues[state.teams[home].league].name,
+                            })
+                        })
+                        .collect();
+                    json!({
+                        "date": day.date,
+                        "weekend": day.is_weekend(),
+                        "games": games,
+                    })
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut out, &json_days)?;
+            writeln!(out)?;
+            eprint!("{}", format_weekend_games(&state, &days));
+        }
+    }
+    Ok(())
+}
+
+fn report(args: &StageArgs) -> Result<(), Box<dyn Error>> {
+    let season = Season::from_path(&args.season)?;
+    let (state, league_distance_matrix, _) = get_matchups(&season, args)?;
+    let travel_scores = get_travel_scores(&state, &league_distance_matrix);
+    let mut notes = String::new();
+    if args.optimal {
+        let (optimal, _, _) = get_total_travel_matchups(&season, args)?;
+        notes += &format_optimality_gap(&season, &optimal, &league_distance_matrix);
+    }
+    if args.objective != Objective::Total {
+        notes += &format_objective_comparison(&season, args, &state, &league_distance_matrix)?;
+    }
+    let mut out = open_output(&args.output)?;
+
+    match args.format {
+        Format::Text => {
+            writeln!(out, "Travel scores:")?;
+            for (team, travel_score) in state.teams.iter().zip(travel_scores.iter()) {
+                writeln!(out, " - {}: {}", team.name, travel_score)?;
+            }
+            writeln!(
+                out,
+                "Total travel score: {}",
+                travel_scores.iter().sum::<i32>()
+            )?;
+            write!(out, "{}", notes)?;
+        }
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["Team", "Travel"])?;
+            for (team, travel_score) in state.teams.iter().zip(travel_scores.iter()) {
+                writer.write_record([&team.name, &travel_score.to_string()])?;
+            }
+            writer.flush()?;
+            eprint!("{}", notes);
+        }
+        Format::Json => {
+            let teams: Vec<_> = state
+                .teams
+                .iter()
+                .zip(travel_scores.iter())
+                .map(|(team, travel_score)| json!({"team": team.name, "travel": travel_score}))
+                .collect();
+            serde_json::to_writer_pretty(&mut out, &teams)?;
+            writeln!(out)?;
+            eprint!("{}", notes);
+        }
+    }
+    Ok(())
+}
+
+fn convert(args: &ConvertArgs) -> Result<(), Box<dyn Error>> {
+    let season = Season::from_path(&args.season)?;
+    let text = match args.format {
+        SeasonFormat::Toml => season.to_toml_string()?,
+        SeasonFormat::Json => season.to_json_string()? + "\n",
+    };
+    open_output(&args.output)?.write_all(text.as_bytes())?;
+    Ok(())
+}
+
+fn grid(args: &GridArgs) -> Result<(), Box<dyn Error>> {
+    let state = Season::from_path(&args.season)?.state();
+    let games = read_grid(File::open(&args.grid)?, &state)?;
+    write_game_list(open_output(&args.output)?, &state, &games)?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match &cli.command {
+        Command::Matchups(args) => matchups(args),
+        Command::Schedule(args) => schedule(args),
+        Command::Report(args) => report(args),
+        Command::Convert(args) => convert(args),
+        Command::Grid(args) => grid(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,357 @@
+use std::collections::VecDeque;
+
+use crate::error::ScheduleError;
+use crate::teams::*;
+
+// Exact alternative to assign_minimum_interleague_games and assign_remaining_games: picks the
+// interleague games that get teams as close to their targets as possible and, among those, have
+// the lowest total travel score.
+//
+// Choosing games is a b-matching problem: each team has a number of games left, and each pair of
+// teams from different leagues can meet up to max_meetings times. Its relaxation is a min-cost
+// flow from every team to every other team (a game counts once in each direction), which gives a
+// lower bound and is often already a valid set of games. Where it isn't, branch and bound splits
+// on a pair that met a fractional number of times.
+
+struct Edge {
+    to: usize,
+    capacity: i32,
+    cost: i64,
+}
+
+struct MinCostFlow {
+    edges: Vec<Edge>,
+    graph: Vec<Vec<usize>>,
+}
+
+impl MinCostFlow {
+    fn new(nodes: usize) -> MinCostFlow {
+        MinCostFlow {
+            edges: vec![],
+            graph: vec![vec![]; nodes],
+        }
+    }
+
+    // Returns the edge index, for reading its flow afterwards.
+    fn add_edge(&mut self, from: usize, to: usize, capacity: i32, cost: i64) -> usize {
+        self.graph[from].push(self.edges.len());
+        self.edges.push(Edge { to, capacity, cost });
+        self.graph[to].push(self.edges.len());
+        self.edges.push(Edge {
+            to: from,
+            capacity: 0,
+            cost: -cost,
+        });
+        self.edges.len() - 2
+    }
+
+    fn flow(&self, edge: usize) -> i32 {
+        self.edges[edge ^ 1].capacity
+    }
+
+    // Maximum flow from source to sink, with the lowest cost among maximum flows. Returns
+    // (flow, cost).
+    fn solve(&mut self, source: usize, sink: usize) -> (i32, i64) {
+        let (mut total_flow, mut total_cost) = (0, 0);
+        loop {
+            // Shortest paths by cost (Bellman-Ford with a queue, since residual costs can be
+            // negative).
+            let mut distance = vec![i64::MAX; self.graph.len()];
+            let mut previous_edge = vec![usize::MAX; self.graph.len()];
+            let mut queued = vec![false; self.graph.len()];
+            let mut queue = VecDeque::from([source]);
+            distance[source] = 0;
+            while let Some(node) = queue.pop_front() {
+                queued[node] = false;
+                for &ei in self.graph[node].iter() {
+                    let edge = &self.edges[ei];
+                    if edge.capacity > 0 && distance[node] + edge.cost < distance[edge.to] {
+                        distance[edge.to] = distance[node] + edge.cost;
+                        previous_edge[edge.to] = ei;
+                        if !queued[edge.to] {
+                            queued[edge.to] = true;
+                            queue.push_back(edge.to);
+                        }
+                    }
+                }
+            }
+            if distance[sink] == i64::MAX {
+                return (total_flow, total_cost);
+            }
+
+            let mut path_flow = i32::MAX;
+            let mut node = sink;
+            while node != source {
+                let ei = previous_edge[node];
+                path_flow = path_flow.min(self.edges[ei].capacity);
+                node = self.edges[ei ^ 1].to;
+            }
+            let mut node = sink;
+            while node != source {
+                let ei = previous_edge[node];
+                self.edges[ei].capacity -= path_flow;
+                self.edges[ei ^ 1].capacity += path_flow;
+                node = self.edges[ei ^ 1].to;
+            }
+            total_flow += path_flow;
+            total_cost += path_flow as i64 * distance[sink];
+        }
+    }
+}
+
+// A branch and bound node: games still wanted per team, meetings still allowed per pair, and
+// games already decided on.
+#[derive(Clone)]
+struct Node {
+    games_left: Vec<i32>,
+    meetings_left: Vec<Vec<i32>>,
+    games: Vec<(usize, usize)>,
+}
+
+struct Search<'a> {
+    distances: &'a Matrix,
+    // Outweighs any travel score, so fewer games short always wins. Even, like travel scores.
+    games_short_weight: i64,
+    // (objective, games) of the best set of games found so far.
+    best: Option<(i64, Vec<(usize, usize)>)>,
+}
+
+impl Search<'_> {
+    fn objective(&self, games_short: i32, travel: i64) -> i64 {
+        self.games_short_weight * games_short as i64 + travel
+    }
+
+    // Objective of a set of games that includes node.games.
+    fn value(&self, node: &Node, games: &[(usize, usize)]) -> i64 {
+        let new_games = (games.len() - node.games.len()) as i32;
+        let games_short = node.games_left.iter().sum::<i32>() - 2 * new_games;
+        self.objective(games_short, self.travel(games))
+    }
+
+    // Games close to the relaxation: every pair meets the rounded down number of times, then the
+    // closest pairs that still have games left meet again.
+    fn round(&self, node: &Node, meetings: &Matrix) -> Vec<(usize, usize)> {
+        let num_teams = node.games_left.len();
+        let mut games_left = node.games_left.clone();
+        let mut meetings_left = node.meetings_left.clone();
+        let mut games = node.games.clone();
+        let mut pairs = Vec::new();
+        for ti0 in 0..num_teams {
+            for ti1 in ti0 + 1..num_teams {
+                let times = meetings[ti0][ti1] / 2;
+                games.extend(std::iter::repeat_n((ti0, ti1), times as usize));
+                games_left[ti0] -= times;
+                games_left[ti1] -= times;
+                meetings_left[ti0][ti1] -= times;
+                pairs.push((ti0, ti1));
+            }
+        }
+        pairs.sort_by_key(|(ti0, ti1)| self.distances[*ti0][*ti1]);
+        for (ti0, ti1) in pairs {
+            while games_left[ti0] > 0 && games_left[ti1] > 0 && meetings_left[ti0][ti1] > 0 {
+                games.push((ti0, ti1));
+                games_left[ti0] -= 1;
+                games_left[ti1] -= 1;
+                meetings_left[ti0][ti1] -= 1;
+            }
+        }
+        games
+    }
+
+    fn travel(&self, games: &[(usize, usize)]) -> i64 {
+        // Both teams count a game's distance in their travel score.
+        games
+            .iter()
+            .map(|(ti0, ti1)| 2 * self.distances[*ti0][*ti1] as i64)
+            .sum()
+    }
+
+    fn explore(&mut self, node: Node) {
+        let num_teams = node.games_left.len();
+        let (source, sink) = (2 * num_teams, 2 * num_teams + 1);
+        let mut flow = MinCostFlow::new(2 * num_teams + 2);
+        let mut pair_edges = Vec::new();
+        for ti in 0..num_teams {
+            flow.add_edge(source, ti, node.games_left[ti], 0);
+            flow.add_edge(num_teams + ti, sink, node.games_left[ti], 0);
+        }
+        for ti0 in 0..num_teams {
+            for ti1 in 0..num_teams {
+                if node.meetings_left[ti0][ti1] > 0 {
+                    let cost = self.distances[ti0][ti1] as i64;
+                    let ei =
+                        flow.add_edge(ti0, num_teams + ti1, node.meetings_left[ti0][ti1], cost);
+                    pair_edges.push((ti0, ti1, ei));
+                }
+            }
+        }
+        let (total_flow, cost) = flow.solve(source, sink);
+        let games_left: i32 = node.games_left.iter().sum();
+        let mut bound = self.objective(games_left - total_flow, cost + self.travel(&node.games));
+        // Every set of games has an even objective.
+        bound += bound % 2;
+        if matches!(&self.best, Some((best, _)) if bound >= *best) {
+            return;
+        }
+
+        // Times each pair meets, counting both directions.
+        let mut meetings = vec![vec![0; num_teams]; num_teams];
+        for (ti0, ti1, ei) in pair_edges {
+            meetings[ti0.min(ti1)][ti0.max(ti1)] += flow.flow(ei);
+        }
+        let fractional = (0..num_teams)
+            .flat_map(|ti0| (ti0 + 1..num_teams).map(move |ti1| (ti0, ti1)))
+            .find(|(ti0, ti1)| meetings[*ti0][*ti1] % 2 == 1);
+        match fractional {
+            None => {
+                let games = self.round(&node, &meetings);
+                self.best = Some((bound, games));
+            }
+            Some((ti0, ti1)) => {
+                let rounded = self.round(&node, &meetings);
+                let value = self.value(&node, &rounded);
+                if !matches!(&self.best, Some((best, _)) if value >= *best) {
+                    self.best = Some((value, rounded));
+                }
+                if value == bound {
+                    return;
+                }
+
+                // Either the pair meets at least once more than rounding down...
+                let at_least = meetings[ti0][ti1] / 2 + 1;
+                if node.games_left[ti0] >= at_least && node.games_left[ti1] >= at_least {
+                    let mut more = node.clone();
+                    more.games_left[ti0] -= at_least;
+                    more.games_left[ti1] -= at_least;
+                    more.meetings_left[ti0][ti1] -= at_least;
+                    more.meetings_left[ti1][ti0] -= at_least;
+                    for _ in 0..at_least {
+                        more.games.push((ti0, ti1));
+                    }
+                    self.explore(more);
+                }
+                // ...or no more than rounding down.
+                let mut fewer = node;
+                fewer.meetings_left[ti0][ti1] = at_least - 1;
+                fewer.meetings_left[ti1][ti0] = at_least - 1;
+                self.explore(fewer);
+            }
+        }
+    }
+}
+
+// Adds the interleague games, at most max_meetings per pair, that leave teams the fewest games
+// short of their targets with the lowest total travel score.
+pub fn assign_optimal_interleague_games(
+    state: &mut State,
+    teams_matrix: &mut Matrix,
+    league_distance_matrix: &Matrix,
+    max_meetings: i32,
+) {
+    let num_teams = state.teams.len();
+    let mut distances = create_matrix_with_dimension(num_teams);
+    let mut meetings_left = create_matrix_with_dimension(num_teams);
+    for ti0 in 0..num_teams {
+        for ti1 in 0..num_teams {
+            let (li0, li1) = (state.teams[ti0].league.0, state.teams[ti1].league.0);
+            if li0 != li1 {
+                distances[ti0][ti1] = get_matrix_val(league_distance_matrix, li0, li1);
+                meetings_left[ti0][ti1] = max_meetings - get_matrix_val(teams_matrix, ti0, ti1);
+            }
+        }
+    }
+    let games_left: Vec<i32> = state
+        .teams
+        .iter()
+        .map(|t| (t.target_games - t.num_games).max(0))
+        .collect();
+    let max_distance = distances.iter().flatten().max().copied().unwrap_or(0) as i64;
+
+    let mut search = Search {
+        distances: &distances,
+        games_short_weight: 2 + 2 * max_distance * games_left.iter().sum::<i32>() as i64,
+        best: None,
+    };
+    search.explore(Node {
+        games_left,
+        meetings_left,
+        games: vec![],
+    });
+    if let Some((_, games)) = search.best {
+        for (ti0, ti1) in games {
+            add_game(&mut state.teams, teams_matrix, ti0, ti1);
+        }
+    }
+}
+
+// Same as get_teams_to_play_against, with interleague games chosen by
+// assign_optimal_interleague_games.
+pub fn get_optimal_teams_to_play_against(
+    state: &mut State,
+    league_distance_matrix: &Matrix,
+    max_meetings: i32,
+) -> Result<Matrix, ScheduleError> {
+    let mut teams_matrix = create_matrix_with_dimension(state.teams.len());
+    assign_in_league_games(state, &mut teams_matrix);
+    assign_optimal_interleague_games(
+        state,
+        &mut teams_matrix,
+        league_distance_matrix,
+        max_meetings,
+    );
+    check_shortfalls(state)?;
+    Ok(teams_matrix)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::optimal::*;
+    use crate::sample_data::*;
+
+    #[test]
+    fn beats_greedy() {
+        let league_distance_matrix = get_district8_city_league_distances();
+        let mut greedy = get_2023_majors_softball_state();
+        greedy.set_target_games(10);
+        get_teams_to_play_against(&mut greedy, &league_distance_matrix).unwrap();
+        let mut optimal = get_2023_majors_softball_state();
+        optimal.set_target_games(10);
+        let matrix =
+            get_optimal_teams_to_play_against(&mut optimal, &league_distance_matrix, 1).unwrap();
+
+        assert!(get_games_short(&optimal) <= get_games_short(&greedy));
+        let total = |state: &State| -> i32 {
+            get_travel_scores(state, &league_distance_matrix)
+                .iter()
+                .sum()
+        };
+        if get_games_short(&optimal) == get_games_short(&greedy) {
+            assert!(total(&optimal) <= total(&greedy));
+        }
+        assert!(matrix.iter().flatten().all(|games| *games <= 2));
+        assert!(optimal.teams.iter().all(|t| t.num_games <= 10));
+    }
+
+    #[test]
+    fn odd_cycle() {
+        // Three one-team leagues, one game each: only two can play, so someone is short. The
+        // flow relaxation would play a half game around the triangle.
+        let mut state = get_state_from_league_info(
+            &[
+                ("A".to_string(), 1),
+                ("B".to_string(), 1),
+                ("C".to_string(), 1),
+            ],
+            1,
+        );
+        let mut league_distance_matrix = create_matrix_with_dimension(3);
+        league_distance_matrix[0][1] = 3;
+        league_distance_matrix[0][2] = 1;
+        league_distance_matrix[1][2] = 2;
+        let mut matrix = create_matrix_with_dimension(3);
+        assign_optimal_interleague_games(&mut state, &mut matrix, &league_distance_matrix, 1);
+        assert_eq!(get_shortfalls(&state), vec![(TeamId(1), 1)]);
+        assert_eq!(matrix[0][2], 1);
+        assert_eq!(matrix.iter().flatten().sum::<i32>(), 1);
+    }
+}
@@ -1,5 +1,8 @@
-use std::collections::HashSet;
+use chrono::{NaiveDate, Weekday};
 
+use crate::calendar::*;
+use crate::error::ScheduleError;
+use crate::home_away::*;
 use crate::interleague_schedule::*;
 use crate::teams::*;
 
@@ -36,23 +39,33 @@ pub fn get_district8_city_league_distances() -> Matrix {
     return league_distance_matrix;
 }
 
-pub fn get_state_from_league_info(league_info: &Vec<(String, usize)>) -> State {
+// Every team starts with a target of target_games.
+pub fn get_state_from_league_info(league_info: &[(String, usize)], target_games: i32) -> State {
     let mut state = State {
         leagues: vec![],
         teams: vec![],
+        rest: Rest::default(),
+        weekend_tolerance: None,
+        rematch_min_days: 0,
     };
     let mut last_index: usize = 0;
     for (li, info) in league_info.iter().enumerate() {
         let name: String = info.0.clone();
         let team_count: usize = info.1;
         state.leagues.push(League {
-            name: name,
+            id: LeagueId(li),
+            name: name.clone(),
             team_index_range: (last_index, last_index + team_count),
+            venues: vec![],
+            meetings: 2,
         });
-        for _ in 0..team_count {
+        for i in 0..team_count {
             state.teams.push(Team {
-                league_index: li,
+                id: TeamId(last_index + i),
+                name: format!("{} {}", name, i + 1),
+                league: LeagueId(li),
                 num_games: 0,
+                target_games,
                 teams_against: vec![],
             });
         }
@@ -62,6 +75,9 @@ pub fn get_state_from_league_info(league_info: &Vec<(String, usize)>) -> State {
     return state;
 }
 
+// Games per team in the sample seasons.
+const SAMPLE_MAX_GAMES: i32 = 14;
+
 pub fn get_2023_majors_softball_state() -> State {
     let league_info: Vec<(String, usize)> = vec![
         ("SL".to_string(), 1),
@@ -73,7 +89,7 @@ pub fn get_2023_majors_softball_state() -> State {
         ("BAL".to_string(), 2),
         ("NC".to_string(), 2),
     ];
-    get_state_from_league_info(&league_info)
+    get_state_from_league_info(&league_info, SAMPLE_MAX_GAMES)
 }
 
 pub fn get_2023_aaa_softball_state() -> State {
@@ -87,7 +103,7 @@ pub fn get_2023_aaa_softball_state() -> State {
         ("BAL".to_string(), 2),
         ("NC".to_string(), 4),
     ];
-    get_state_from_league_info(&league_info)
+    get_state_from_league_info(&league_info, SAMPLE_MAX_GAMES)
 }
 
 pub fn get_2024_aaa_softball_state() -> State {
@@ -101,99 +117,166 @@ pub fn get_2024_aaa_softball_state() -> State {
         ("BAL".to_string(), 1),
         ("NC".to_string(), 3),
     ];
-    get_state_from_league_info(&league_info)
+    get_state_from_league_info(&league_info, SAMPLE_MAX_GAMES)
+}
+// "3/23" in the given year.
+fn get_date(month_day: &str, year: i32) -> Result<NaiveDate, ScheduleError> {
+    NaiveDate::parse_from_str(&format!("{}/{}", month_day, year), "%m/%d/%Y")
+        .map_err(|_| ScheduleError::BadDate(month_day.to_string()))
 }
-fn get_day_from_day_info(day_info: (String, bool, Vec<usize>), num_teams: usize) -> Day {
-    let not_these_teams = HashSet::from_iter(day_info.2);
-    let teams_playing = &HashSet::from_iter(0..num_teams) - &not_these_teams;
-    assert!(teams_playing.len() % 2 == 0);
-    Day {
-        date: day_info.0,
-        is_weekend: day_info.1,
-        teams_playing: teams_playing,
-        games: vec![],
+
+// Teams are named, or a league name stands for all of its teams.
+fn get_day_from_day_info(
+    day_info: (&str, Vec<&str>),
+    year: i32,
+    state: &State,
+) -> Result<Day, ScheduleError> {
+    let date = get_date(day_info.0, year)?;
+    let refs: Vec<TeamRef> = day_info
+        .1
+        .iter()
+        .map(|name| TeamRef::Name(name.to_string()))
+        .collect();
+    let teams_playing =
+        state
+            .teams_except(refs.iter())
+            .map_err(|team| ScheduleError::UnknownTeam {
+                date,
+                team: team.to_string(),
+            })?;
+    if teams_playing.len() % 2 != 0 {
+        return Err(ScheduleError::OddTeamsPlaying {
+            date,
+            count: teams_playing.len(),
+        });
     }
+    Ok(Day::new(date, teams_playing))
 }
 
-pub fn get_2023_majors_softball_days(num_teams: usize) -> Vec<Day> {
+// The 2023 days were drawn up against the 2024 calendar, so that's the year their weekends come
+// from.
+pub fn get_2023_majors_softball_days(state: &State) -> Result<Vec<Day>, ScheduleError> {
     vec![
-        ("3/23".to_string(), true, vec![12]),
-        ("3/27".to_string(), false, vec![10]),
-        ("3/30".to_string(), true, vec![11]),
-        ("4/03".to_string(), false, vec![9]),
-        ("4/13".to_string(), true, vec![1, 2, 3, 4, 5, 7, 10, 11, 12]),
-        ("4/17".to_string(), false, vec![8]),
-        ("4/20".to_string(), true, vec![0]),
-        ("4/24".to_string(), false, vec![0]),
-        ("4/27".to_string(), true, vec![9]),
-        ("4/29".to_string(), false, vec![6]),
-        ("5/01".to_string(), false, vec![5]),
-        ("5/04".to_string(), true, vec![8]),
-        ("5/06".to_string(), false, vec![4]),
-        ("5/08".to_string(), false, vec![3]),
-        ("5/11".to_string(), true, vec![7]),
-        ("5/13".to_string(), false, vec![2]),
-        ("5/15".to_string(), false, vec![1]),
-        ("5/18".to_string(), true, vec![6]),
+        ("3/23", vec!["NC 2"]),
+        ("3/27", vec!["BAL 2"]),
+        ("3/30", vec!["NC 1"]),
+        ("4/03", vec!["BAL 1"]),
+        ("4/13", vec!["NE", "MAG", "NW 1", "BAL 2", "NC"]),
+        ("4/17", vec!["RUG 1"]),
+        ("4/20", vec!["SL 1"]),
+        ("4/24", vec!["SL 1"]),
+        ("4/27", vec!["BAL 1"]),
+        ("4/29", vec!["QA 1"]),
+        ("5/01", vec!["MAG 2"]),
+        ("5/04", vec!["RUG 1"]),
+        ("5/06", vec!["MAG 1"]),
+        ("5/08", vec!["NE 3"]),
+        ("5/11", vec!["NW 1"]),
+        ("5/13", vec!["NE 2"]),
+        ("5/15", vec!["NE 1"]),
+        ("5/18", vec!["QA 1"]),
     ]
     .into_iter()
-    .map(|di| return get_day_from_day_info(di, num_teams))
+    .map(|di| get_day_from_day_info(di, 2024, state))
     .collect()
 }
 
-pub fn get_2023_aaa_softball_days(num_teams: usize) -> Vec<Day> {
+pub fn get_2023_aaa_softball_days(state: &State) -> Result<Vec<Day>, ScheduleError> {
     vec![
-        ("3/23".to_string(), true, vec![22]),
-        ("3/27".to_string(), false, vec![3]),
-        ("3/30".to_string(), true, vec![21]),
-        ("4/03".to_string(), false, vec![4]),
+        ("3/23", vec!["NC 4"]),
+        ("3/27", vec!["NE 1"]),
+        ("3/30", vec!["NC 3"]),
+        ("4/03", vec!["NE 2"]),
         (
-            "4/13".to_string(),
-            true,
+            "4/13",
             vec![
-                4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22,
+                "NE 2", "NE 3", "NE 4", "MAG", "QA", "NW", "RUG", "BAL", "NC",
             ],
         ),
-        ("4/17".to_string(), false, vec![5]),
-        ("4/20".to_string(), true, vec![0, 1, 2]),
-        ("4/24".to_string(), false, vec![0, 1, 2]),
-        ("4/27".to_string(), true, vec![19]),
-        ("4/29".to_string(), false, vec![6]),
-        ("5/04".to_string(), true, vec![18]),
-        ("5/06".to_string(), false, vec![8]),
+        ("4/17", vec!["NE 3"]),
+        ("4/20", vec!["SL"]),
+        ("4/24", vec!["SL"]),
+        ("4/27", vec!["NC 1"]),
+        ("4/29", vec!["NE 4"]),
+        ("5/04", vec!["BAL 2"]),
+        ("5/06", vec!["MAG 2"]),
         (
-            "5/08".to_string(),
-            false,
-            vec![3, 7, 9, 10, 11, 12, 13, 14, 15],
+            "5/08",
+            vec![
+                "NE 1", "MAG 1", "MAG 3", "MAG 4", "QA", "NW 1", "RUG 1", "RUG 2",
+            ],
         ),
-        ("5/11".to_string(), true, vec![17]),
-        ("5/13".to_string(), false, vec![10]),
-        ("5/18".to_string(), true, vec![16]),
+        ("5/11", vec!["BAL 1"]),
+        ("5/13", vec!["MAG 4"]),
+        ("5/18", vec!["RUG 3"]),
     ]
     .into_iter()
-    .map(|di| return get_day_from_day_info(di, num_teams))
+    .map(|di| get_day_from_day_info(di, 2024, state))
     .collect()
 }
 
-pub fn get_2024_aaa_softball_days(num_teams: usize) -> Vec<Day> {
-    vec![
-        ("3/23".to_string(), true, vec![]),
-        ("3/26".to_string(), false, vec![]),
-        ("3/30".to_string(), true, vec![]),
-        ("4/02".to_string(), false, vec![]),
-        ("4/16".to_string(), false, vec![]),
-        ("4/20".to_string(), true, vec![]),
-        ("4/23".to_string(), false, vec![]),
-        ("4/27".to_string(), true, vec![]),
-        ("4/30".to_string(), false, vec![]),
-        ("5/04".to_string(), true, vec![]),
-        ("5/07".to_string(), false, vec![]),
-        ("5/11".to_string(), true, vec![]),
-        ("5/14".to_string(), false, vec![]),
-        ("5/18".to_string(), true, vec![]),
-        ("5/21".to_string(), false, vec![]),
-    ]
-    .into_iter()
-    .map(|di| return get_day_from_day_info(di, num_teams))
-    .collect()
+// The 2023 AAA season ready to schedule, with every team's target set to its number of available
+// days so the days as published can be filled exactly. A fixture for tests and benches.
+pub fn get_2023_aaa_filled_config() -> Result<(State, ScheduleConfig), ScheduleError> {
+    let league_distance_matrix = get_district8_city_league_distances();
+    let mut state = get_2023_aaa_softball_state();
+    let days = get_2023_aaa_softball_days(&state)?;
+    for team in state.teams.iter_mut() {
+        team.target_games = days
+            .iter()
+            .filter(|d| d.teams_playing.contains(team.id))
+            .count() as i32;
+    }
+    let team_matrix = get_teams_to_play_against(&mut state, &league_distance_matrix)?;
+    let mut games = get_remaining_games(&state, &team_matrix, &league_distance_matrix);
+    assign_home_games(&mut games, &get_home_games(&team_matrix));
+    let config = ScheduleConfig::new(days, games, &state)?;
+    Ok((state, config))
+}
+
+// Tuesdays and Saturdays from 3/23 to 5/21, skipping spring break.
+pub fn get_2024_aaa_softball_calendar() -> CalendarRules {
+    let date = |month, day| NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+    CalendarRules {
+        start: date(3, 23),
+        end: date(5, 21),
+        weekdays: vec![Weekday::Tue, Weekday::Sat],
+        holidays: vec![Holiday {
+            name: "Spring break".to_string(),
+            first: date(4, 3),
+            last: date(4, 15),
+        }],
+        blackouts: vec![],
+    }
+}
+
+pub fn get_2024_aaa_softball_days(state: &State) -> Result<Vec<Day>, ScheduleError> {
+    get_days(&get_2024_aaa_softball_calendar(), state)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sample_data::*;
+
+    #[test]
+    fn weekends_match_hand_entered_flags() {
+        // The weekend flag each day carried before weekends were derived from dates.
+        let majors = [
+            true, false, true, false, true, false, true, false, true, false, false, true, false,
+            false, true, false, false, true,
+        ];
+        let aaa = [
+            true, false, true, false, true, false, true, false, true, false, true, false, false,
+            true, false, true,
+        ];
+        let state = get_2023_majors_softball_state();
+        let days = get_2023_majors_softball_days(&state).unwrap();
+        let weekends: Vec<bool> = days.iter().map(|d| d.is_weekend()).collect();
+        assert_eq!(weekends, majors);
+
+        let state = get_2023_aaa_softball_state();
+        let days = get_2023_aaa_softball_days(&state).unwrap();
+        let weekends: Vec<bool> = days.iter().map(|d| d.is_weekend()).collect();
+        assert_eq!(weekends, aaa);
+    }
 }
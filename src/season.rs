@@ -0,0 +1,701 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use crate::calendar::CalendarRules;
+use crate::distances::*;
+use crate::error::ScheduleError;
+use crate::interleague_schedule::*;
+use crate::sample_data::get_state_from_league_info;
+use crate::team_set::TeamSet;
+use crate::teams::*;
+
+// A season described in a TOML or JSON file, replacing the per-season functions in sample_data.
+//
+// max_games = 14
+//
+// [[leagues]]
+// name = "SL"
+// teams = 4
+// meetings = 1
+// venues = [{ name = "Shoreline Park", fields = 2, dates = { 2023-04-13 = 1 } }]
+//
+// [[distances]]
+// leagues = ["SL", "NE"]
+// distance = 3
+//
+// Distances not listed are worked out from a CSV of drive times between leagues (see
+// distances::read_drive_times), given relative to the season file, which then has to cover every
+// one of them, or otherwise from the leagues' home field locations. Either can be bucketed into
+// tiers, as upper bounds in minutes or kilometers for distance scores 1, 2, ..., set at the top
+// level. Without tiers, worked out distances are raw minutes or kilometers and can't be mixed
+// with listed ones:
+//
+// drive_times = "drive_times.csv"
+// distance_tiers = [5.0, 10.0, 20.0]
+//
+// [[leagues]]
+// name = "NW"
+// teams = 1
+// location = { latitude = 47.69, longitude = -122.37 }
+//
+// [[days]]
+// date = "2023-03-23"
+// unavailable = ["NC 4", "SL"]
+//
+// Teams are named by league and number from 1, like "NC 4", and a league name stands for all of
+// its teams. Indices across all leagues, from 0, also work.
+//
+// Instead of (or as well as) listing days, they can be generated from a calendar; see
+// calendar::CalendarRules:
+//
+// [calendar]
+// start = "2024-03-23"
+// end = "2024-05-21"
+// weekdays = ["Tue", "Sat"]
+//
+// Optionally, days without a game each team needs between games, and groups of teams sharing
+// coaches or pitchers that need more:
+//
+// [rest]
+// min_days = 1
+// groups = [{ teams = ["NE 2", "QA 2"], min_days = 2 }]
+//
+// and the most weekend games a team can have more than another, set at the top level. Teams with
+// smaller targets count their weekend games scaled up to the largest target:
+//
+// weekend_tolerance = 1
+//
+// Fewest days without a meeting between two meetings of the same teams is set there too, counted
+// like rest days:
+//
+// rematch_min_days = 14
+//
+// Every team plays max_games games, except teams with a target of their own:
+//
+// [[targets]]
+// team = "NE 3"
+// games = 10
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Season {
+    pub max_games: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weekend_tolerance: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rematch_min_days: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drive_times: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distance_tiers: Option<Vec<f64>>,
+    pub leagues: Vec<LeagueInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub distances: Vec<DistanceInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calendar: Option<CalendarRules>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<DayInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rest: Option<RestInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<TargetInfo>,
+    // Directory of the season file, for finding the drive times.
+    #[serde(skip)]
+    pub base_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LeagueInfo {
+    pub name: String,
+    pub teams: usize,
+    // Times each pair of teams in the league plays each other, 2 if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meetings: Option<usize>,
+    // Home field, for working out distances.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    // Leagues without venues can host any number of games.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub venues: Vec<VenueInfo>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct VenueInfo {
+    pub name: String,
+    pub fields: usize,
+    // Fields available on specific dates, overriding `fields`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dates: BTreeMap<NaiveDate, usize>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DistanceInfo {
+    pub leagues: (String, String),
+    pub distance: i32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DayInfo {
+    pub date: NaiveDate,
+    // Teams that can't play on this date.
+    #[serde(default)]
+    pub unavailable: Vec<TeamRef>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestInfo {
+    #[serde(default)]
+    pub min_days: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<RestGroupInfo>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestGroupInfo {
+    pub teams: Vec<TeamRef>,
+    pub min_days: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetInfo {
+    pub team: TeamRef,
+    pub games: u32,
+}
+
+impl Season {
+    // Reads a season file. Files ending in ".json" are parsed as JSON, everything else as TOML.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Season, ScheduleError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(ScheduleError::Io)?;
+        let mut season = if path.extension().is_some_and(|ext| ext == "json") {
+            Season::from_json_str(&text)?
+        } else {
+            Season::from_toml_str(&text)?
+        };
+        season.base_dir = path.parent().map(Path::to_path_buf);
+        Ok(season)
+    }
+
+    pub fn from_toml_str(text: &str) -> Result<Season, ScheduleError> {
+        let season: Season =
+            toml::from_str(text).map_err(|e| ScheduleError::Parse(e.to_string()))?;
+        season.validate()?;
+        Ok(season)
+    }
+
+    pub fn from_json_str(text: &str) -> Result<Season, ScheduleError> {
+        let season: Season =
+            serde_json::from_str(text).map_err(|e| ScheduleError::Parse(e.to_string()))?;
+        season.validate()?;
+        Ok(season)
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, ScheduleError> {
+        toml::to_string(self).map_err(|e| ScheduleError::Serialize(e.to_string()))
+    }
+
+    pub fn to_json_string(&self) -> Result<String, ScheduleError> {
+        serde_json::to_string_pretty(self).map_err(|e| ScheduleError::Serialize(e.to_string()))
+    }
+
+    fn validate(&self) -> Result<(), ScheduleError> {
+        if self.max_games <= 0 {
+            return Err(ScheduleError::NonPositiveMaxGames(self.max_games));
+        }
+        if self.leagues.is_empty() {
+            return Err(ScheduleError::NoLeagues);
+        }
+        let mut seen = HashSet::new();
+        for league in self.leagues.iter() {
+            if league.teams == 0 {
+                return Err(ScheduleError::EmptyLeague(league.name.clone()));
+            }
+            if !seen.insert(league.name.as_str()) {
+                return Err(ScheduleError::DuplicateLeague(league.name.clone()));
+            }
+        }
+        let num_teams: usize = self.leagues.iter().map(|l| l.teams).sum();
+        if num_teams > TeamSet::MAX_TEAMS {
+            return Err(ScheduleError::TooManyTeams(num_teams));
+        }
+
+        // Every pair of different leagues needs exactly one listed distance, or a way to work it
+        // out.
+        let mut distances: HashMap<(usize, usize), i32> = HashMap::new();
+        for d in self.distances.iter() {
+            let (li0, li1) = self.league_pair(&d.leagues.0, &d.leagues.1)?;
+            if d.distance < 0 {
+                return Err(ScheduleError::NegativeDistance(
+                    d.leagues.0.clone(),
+                    d.leagues.1.clone(),
+                ));
+            }
+            if distances.insert((li0, li1), d.distance).is_some() {
+                return Err(ScheduleError::DuplicateDistance(
+                    d.leagues.0.clone(),
+                    d.leagues.1.clone(),
+                ));
+            }
+        }
+        if let Some(tiers) = &self.distance_tiers {
+            validate_tiers(tiers)?;
+        }
+        for league in self.leagues.iter() {
+            if let Some(location) = &league.location {
+                if !(-90.0..=90.0).contains(&location.latitude)
+                    || !(-180.0..=180.0).contains(&location.longitude)
+                {
+                    return Err(ScheduleError::BadLocation(league.name.clone()));
+                }
+            }
+        }
+        let located = |li: usize| self.leagues[li].location.is_some();
+        for li0 in 0..self.leagues.len() {
+            for li1 in li0 + 1..self.leagues.len() {
+                if distances.contains_key(&(li0, li1)) {
+                    continue;
+                }
+                if self.drive_times.is_none() && !(located(li0) && located(li1)) {
+                    return Err(ScheduleError::MissingDistance(
+                        self.leagues[li0].name.clone(),
+                        self.leagues[li1].name.clone(),
+                    ));
+                }
+                if self.distance_tiers.is_none() && !distances.is_empty() {
+                    return Err(ScheduleError::UntieredDistances);
+                }
+            }
+        }
+
+        let teams = self.teams();
+        if let Some(calendar) = &self.calendar {
+            calendar.validate(&teams)?;
+        }
+        let days = self.day_infos();
+        if days.is_empty() {
+            return Err(ScheduleError::NoDays);
+        }
+        let mut dates = HashSet::new();
+        for day in days.iter() {
+            if !dates.insert(day.date) {
+                return Err(ScheduleError::DuplicateDate(day.date));
+            }
+        }
+        for day in days.iter() {
+            let count = teams
+                .teams_except(day.unavailable.iter())
+                .map_err(|team| ScheduleError::UnknownTeam {
+                    date: day.date,
+                    team: team.to_string(),
+                })?
+                .len();
+            if !count.is_multiple_of(2) {
+                return Err(ScheduleError::OddTeamsPlaying {
+                    date: day.date,
+                    count,
+                });
+            }
+        }
+        for group in self.rest.iter().flat_map(|r| r.groups.iter()) {
+            teams
+                .resolve_all(group.teams.iter())
+                .map_err(|team| ScheduleError::UnknownRestTeam(team.to_string()))?;
+        }
+        for target in self.targets.iter() {
+            teams
+                .resolve(&target.team)
+                .ok_or_else(|| ScheduleError::UnknownTargetTeam(target.team.to_string()))?;
+        }
+        for venue in self.leagues.iter().flat_map(|l| l.venues.iter()) {
+            if let Some(date) = venue.dates.keys().find(|date| !dates.contains(*date)) {
+                return Err(ScheduleError::UnknownVenueDate {
+                    venue: venue.name.clone(),
+                    date: *date,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // League indices for a pair of names, smallest first.
+    fn league_pair(&self, name0: &str, name1: &str) -> Result<(usize, usize), ScheduleError> {
+        let li0 = self.league_index(name0)?;
+        let li1 = self.league_index(name1)?;
+        Ok((li0.min(li1), li0.max(li1)))
+    }
+
+    fn league_index(&self, name: &str) -> Result<usize, ScheduleError> {
+        self.leagues
+            .iter()
+            .position(|l| l.name == name)
+            .ok_or_else(|| ScheduleError::UnknownLeague(name.to_string()))
+    }
+
+    pub fn num_teams(&self) -> usize {
+        self.leagues.iter().map(|l| l.teams).sum()
+    }
+
+    // Just the leagues and teams, for resolving team references.
+    fn teams(&self) -> State {
+        let league_info: Vec<(String, usize)> = self
+            .leagues
+            .iter()
+            .map(|l| (l.name.clone(), l.teams))
+            .collect();
+        get_state_from_league_info(&league_info, self.max_games)
+    }
+
+    pub fn state(&self) -> State {
+        let mut state = self.teams();
+        for (league, info) in state.leagues.iter_mut().zip(self.leagues.iter()) {
+            league.meetings = info.meetings.unwrap_or(2);
+            league.venues = info
+                .venues
+                .iter()
+                .map(|v| Venue {
+                    name: v.name.clone(),
+                    fields: v.fields,
+                    fields_on: v.dates.clone(),
+                })
+                .collect();
+        }
+        for target in self.targets.iter() {
+            for id in state.resolve(&target.team).unwrap_or_default() {
+                state.teams[id].target_games = target.games as i32;
+            }
+        }
+        state.weekend_tolerance = self.weekend_tolerance;
+        state.rematch_min_days = self.rematch_min_days.unwrap_or(0);
+        if let Some(rest) = &self.rest {
+            state.rest = Rest {
+                min_days: rest.min_days,
+                groups: rest
+                    .groups
+                    .iter()
+                    .map(|g| RestGroup {
+                        teams: state.resolve_all(g.teams.iter()).unwrap_or_default(),
+                        min_days: g.min_days,
+                    })
+                    .collect(),
+            };
+        }
+        state
+    }
+
+    // Listed distances first, then drive times, then distances between home fields.
+    pub fn league_distance_matrix(&self) -> Result<Matrix, ScheduleError> {
+        let drive_times = match &self.drive_times {
+            Some(path) => {
+                let path = match &self.base_dir {
+                    Some(dir) => dir.join(path),
+                    None => path.clone(),
+                };
+                let names: Vec<String> = self.leagues.iter().map(|l| l.name.clone()).collect();
+                Some(read_drive_times(File::open(path)?, &names)?)
+            }
+            None => None,
+        };
+        let tiers = self.distance_tiers.as_deref();
+
+        let mut distances: HashMap<(usize, usize), i32> = HashMap::new();
+        for d in self.distances.iter() {
+            distances.insert(self.league_pair(&d.leagues.0, &d.leagues.1)?, d.distance);
+        }
+        for li0 in 0..self.leagues.len() {
+            for li1 in li0 + 1..self.leagues.len() {
+                if distances.contains_key(&(li0, li1)) {
+                    continue;
+                }
+                let names = || {
+                    (
+                        self.leagues[li0].name.clone(),
+                        self.leagues[li1].name.clone(),
+                    )
+                };
+                // Drive times, when given, have to cover every pair so minutes and kilometers
+                // don't get mixed.
+                let minutes_or_km = match &drive_times {
+                    Some(drive_times) => match drive_times.get(&(li0, li1)) {
+                        Some(minutes) => *minutes,
+                        None => {
+                            let (a, b) = names();
+                            return Err(ScheduleError::MissingDriveTime(a, b));
+                        }
+                    },
+                    None => match (&self.leagues[li0].location, &self.leagues[li1].location) {
+                        (Some(a), Some(b)) => get_distance_km(a, b),
+                        _ => {
+                            let (a, b) = names();
+                            return Err(ScheduleError::MissingDistance(a, b));
+                        }
+                    },
+                };
+                distances.insert((li0, li1), get_distance_score(minutes_or_km, tiers));
+            }
+        }
+
+        let mut matrix = create_matrix_with_dimension(self.leagues.len());
+        for ((li0, li1), distance) in distances {
+            matrix[li0][li1] = distance;
+        }
+        Ok(matrix)
+    }
+
+    // Calendar days followed by listed days, in date order.
+    fn day_infos(&self) -> Vec<DayInfo> {
+        let mut days: Vec<DayInfo> = match &self.calendar {
+            Some(calendar) => calendar
+                .dates()
+                .into_iter()
+                .map(|date| DayInfo {
+                    date,
+                    unavailable: calendar.unavailable(date),
+                })
+                .collect(),
+            None => vec![],
+        };
+        days.extend(self.days.iter().cloned());
+        days.sort_by_key(|d| d.date);
+        days
+    }
+
+    pub fn days(&self) -> Vec<Day> {
+        let teams = self.teams();
+        self.day_infos()
+            .iter()
+            .map(|d| {
+                let teams_playing = teams.teams_except(d.unavailable.iter()).unwrap_or_default();
+                Day::new(d.date, teams_playing)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sample_data::*;
+    use crate::season::*;
+    use crate::teams::get_matrix_val;
+
+    const SEASON_2023_AAA: &str = include_str!("../seasons/2023_aaa_softball.toml");
+
+    #[test]
+    fn matches_sample_data() {
+        let season = Season::from_toml_str(SEASON_2023_AAA).unwrap();
+        assert_eq!(season.max_games, 14);
+
+        // Distances are keyed by league name, so they follow the league order in the file.
+        let matrix = season.league_distance_matrix().unwrap();
+        assert_eq!(get_matrix_val(&matrix, 0, 1), 3); // SL - NE
+        assert_eq!(get_matrix_val(&matrix, 0, 2), 4); // SL - MAG
+        assert_eq!(get_matrix_val(&matrix, 1, 5), 1); // NE - RUG
+        assert_eq!(get_matrix_val(&matrix, 6, 7), 1); // BAL - NC
+
+        let state = season.state();
+        let exp_state = get_2023_aaa_softball_state();
+        assert_eq!(format!("{:?}", state), format!("{:?}", exp_state));
+
+        let days = season.days();
+        let exp_days = get_2023_aaa_softball_days(&exp_state).unwrap();
+        assert_eq!(days.len(), exp_days.len());
+        for (day, exp_day) in days.iter().zip(exp_days.iter()) {
+            assert_eq!(day.date, exp_day.date);
+            assert_eq!(day.is_weekend(), exp_day.is_weekend());
+            assert_eq!(day.teams_playing, exp_day.teams_playing);
+        }
+
+        // Targets fill every day a team is available, so the season can be scheduled as is.
+        for team in state.teams.iter() {
+            let available = days
+                .iter()
+                .filter(|d| d.teams_playing.contains(team.id))
+                .count();
+            assert_eq!(team.target_games as usize, available, "{}", team.name);
+        }
+    }
+
+    #[test]
+    fn calendar() {
+        let text = "max_games = 2\n\
+                    distances = [{ leagues = [\"A\", \"B\"], distance = 1 }]\n\
+                    [[leagues]]\nname = \"A\"\nteams = 1\n\
+                    [[leagues]]\nname = \"B\"\nteams = 1\n\
+                    [calendar]\nstart = \"2024-03-23\"\nend = \"2024-04-06\"\n\
+                    weekdays = [\"Tue\", \"Saturday\"]\n\
+                    holidays = [{ name = \"Spring break\", first = \"2024-04-03\", last = \"2024-04-15\" }]\n\
+                    [[days]]\ndate = \"2024-03-24\"\n";
+        let season = Season::from_toml_str(text).unwrap();
+        let dates: Vec<String> = season.days().iter().map(|d| d.date.to_string()).collect();
+        assert_eq!(
+            dates,
+            vec![
+                "2024-03-23",
+                "2024-03-24",
+                "2024-03-26",
+                "2024-03-30",
+                "2024-04-02"
+            ]
+        );
+
+        let round_trip = Season::from_toml_str(&season.to_toml_string().unwrap()).unwrap();
+        assert_eq!(round_trip.days().len(), 5);
+    }
+
+    #[test]
+    fn json() {
+        let text = r#"{
+            "max_games": 2,
+            "leagues": [
+                {"name": "A", "teams": 1, "venues": [{"name": "Park", "fields": 2, "dates": {"2023-03-23": 1}}]},
+                {"name": "B", "teams": 1}
+            ],
+            "distances": [{"leagues": ["B", "A"], "distance": 2}],
+            "days": [{"date": "2023-03-23"}]
+        }"#;
+        let season = Season::from_json_str(text).unwrap();
+        assert_eq!(
+            season.league_distance_matrix().unwrap(),
+            vec![vec![0, 2], vec![0, 0]]
+        );
+        assert_eq!(season.days()[0].teams_playing.len(), 2);
+        assert!(!season.days()[0].is_weekend());
+        let state = season.state();
+        let date = |day| NaiveDate::from_ymd_opt(2023, 3, day).unwrap();
+        assert_eq!(state.leagues[0].fields_on(date(23)), Some(1));
+        assert_eq!(state.leagues[0].fields_on(date(27)), Some(2));
+        assert_eq!(state.leagues[1].fields_on(date(23)), None);
+
+        let round_trip = Season::from_toml_str(&season.to_toml_string().unwrap()).unwrap();
+        assert_eq!(
+            round_trip.to_json_string().unwrap(),
+            season.to_json_string().unwrap()
+        );
+    }
+
+    #[test]
+    fn locations() {
+        let text = "max_games = 2\n\
+                    distance_tiers = [5.0, 10.0]\n\
+                    distances = [{ leagues = [\"A\", \"C\"], distance = 9 }]\n\
+                    [[leagues]]\nname = \"A\"\nteams = 1\n\
+                    location = { latitude = 47.60, longitude = -122.33 }\n\
+                    [[leagues]]\nname = \"B\"\nteams = 1\n\
+                    location = { latitude = 47.66, longitude = -122.33 }\n\
+                    [[leagues]]\nname = \"C\"\nteams = 2\n\
+                    location = { latitude = 47.74, longitude = -122.33 }\n\
+                    [[days]]\ndate = \"2023-03-23\"\n";
+        let season = Season::from_toml_str(text).unwrap();
+        // A - B is about 7 km, B - C about 9 km and A - C is listed.
+        assert_eq!(
+            season.league_distance_matrix().unwrap(),
+            vec![vec![0, 2, 9], vec![0, 0, 2], vec![0, 0, 0]]
+        );
+
+        let unlocated = text.replace("location = { latitude = 47.66, longitude = -122.33 }\n", "");
+        assert!(matches!(
+            Season::from_toml_str(&unlocated),
+            Err(ScheduleError::MissingDistance(a, b)) if a == "A" && b == "B"
+        ));
+        let bad_tiers = text.replace("[5.0, 10.0]", "[0.0]");
+        assert!(matches!(
+            Season::from_toml_str(&bad_tiers),
+            Err(ScheduleError::BadTiers(_))
+        ));
+        let untiered = text.replace("distance_tiers = [5.0, 10.0]\n", "");
+        assert!(matches!(
+            Season::from_toml_str(&untiered),
+            Err(ScheduleError::UntieredDistances)
+        ));
+        let bad_location = text.replace("latitude = 47.74", "latitude = 147.74");
+        assert!(matches!(
+            Season::from_toml_str(&bad_location),
+            Err(ScheduleError::BadLocation(league)) if league == "C"
+        ));
+
+        // Drive times missing B - C don't fall back on locations.
+        let path = std::env::temp_dir().join("interleague_schedule_drive_times.csv");
+        std::fs::write(&path, "From,To,Minutes\nA,B,4\n").unwrap();
+        let drive_times = text.replace(
+            "distance_tiers",
+            &format!("drive_times = {:?}\ndistance_tiers", path),
+        );
+        let season = Season::from_toml_str(&drive_times).unwrap();
+        assert!(matches!(
+            season.league_distance_matrix(),
+            Err(ScheduleError::MissingDriveTime(a, b)) if a == "B" && b == "C"
+        ));
+        std::fs::write(&path, "From,To,Minutes\nA,B,4\nC,B,12\n").unwrap();
+        assert_eq!(
+            season.league_distance_matrix().unwrap(),
+            vec![vec![0, 1, 9], vec![0, 0, 3], vec![0, 0, 0]]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn errors() {
+        let base = |distances: &str, unavailable: &str| {
+            format!(
+                "max_games = 2\n\
+                 distances = {}\n\
+                 [[leagues]]\nname = \"A\"\nteams = 1\n\
+                 [[leagues]]\nname = \"B\"\nteams = 1\n\
+                 [[days]]\ndate = \"2023-03-23\"\nunavailable = {}\n",
+                distances, unavailable
+            )
+        };
+        let ab = "[{ leagues = [\"A\", \"B\"], distance = 1 }]";
+
+        assert!(Season::from_toml_str(&base(ab, "[]")).is_ok());
+        assert!(matches!(
+            Season::from_toml_str(&base("[]", "[]")),
+            Err(ScheduleError::MissingDistance(..))
+        ));
+        assert!(matches!(
+            Season::from_toml_str(&base(&ab.replace("\"B\"", "\"C\""), "[]")),
+            Err(ScheduleError::UnknownLeague(name)) if name == "C"
+        ));
+        assert!(matches!(
+            Season::from_toml_str(&base(ab, "[0]")),
+            Err(ScheduleError::OddTeamsPlaying { count: 1, .. })
+        ));
+        assert!(matches!(
+            Season::from_toml_str(&base(ab, "[2]")),
+            Err(ScheduleError::UnknownTeam { team, .. }) if team == "2"
+        ));
+        assert!(Season::from_toml_str(&base(ab, "[\"A 1\", \"B\"]")).is_ok());
+        assert!(matches!(
+            Season::from_toml_str(&base(ab, "[\"A 2\"]")),
+            Err(ScheduleError::UnknownTeam { team, .. }) if team == "\"A 2\""
+        ));
+        assert!(matches!(
+            Season::from_toml_str(&base(ab, "\"x\"")),
+            Err(ScheduleError::Parse(_))
+        ));
+        let rest = base(ab, "[]") + "[rest]\ngroups = [{ teams = [0, 2], min_days = 2 }]\n";
+        assert!(matches!(
+            Season::from_toml_str(&rest),
+            Err(ScheduleError::UnknownRestTeam(team)) if team == "2"
+        ));
+        let twice = base(ab, "[]") + "[[days]]\ndate = \"2023-03-23\"\n";
+        assert!(matches!(
+            Season::from_toml_str(&twice),
+            Err(ScheduleError::DuplicateDate(date)) if date.to_string() == "2023-03-23"
+        ));
+        let calendar = base(ab, "[]")
+            + "[calendar]\nstart = \"2023-03-20\"\nend = \"2023-03-31\"\nweekdays = [\"Thu\"]\n";
+        assert!(matches!(
+            Season::from_toml_str(&calendar),
+            Err(ScheduleError::DuplicateDate(date)) if date.to_string() == "2023-03-23"
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+
+use crate::teams::TeamId;
+
+// A set of teams as a fixed-width bitset, one bit per TeamId. It's Copy and as cheap to copy as
+// an integer, which matters since the scheduler keeps several per search branch.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TeamSet(u128);
+
+impl TeamSet {
+    pub const MAX_TEAMS: usize = 128;
+
+    pub fn new() -> TeamSet {
+        TeamSet(0)
+    }
+
+    fn bit(id: TeamId) -> u128 {
+        assert!(
+            id.0 < TeamSet::MAX_TEAMS,
+            "team {} doesn't fit in a TeamSet",
+            id
+        );
+        1 << id.0
+    }
+
+    pub fn insert(&mut self, id: TeamId) {
+        self.0 |= TeamSet::bit(id);
+    }
+
+    pub fn remove(&mut self, id: TeamId) {
+        self.0 &= !TeamSet::bit(id);
+    }
+
+    pub fn contains(&self, id: TeamId) -> bool {
+        id.0 < TeamSet::MAX_TEAMS && self.0 & (1 << id.0) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_disjoint(&self, other: &TeamSet) -> bool {
+        self.0 & other.0 == 0
+    }
+
+    pub fn union(&self, other: &TeamSet) -> TeamSet {
+        TeamSet(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: &TeamSet) -> TeamSet {
+        TeamSet(self.0 & other.0)
+    }
+
+    pub fn difference(&self, other: &TeamSet) -> TeamSet {
+        TeamSet(self.0 & !other.0)
+    }
+
+    // Teams in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = TeamId> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let ti = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            Some(TeamId(ti))
+        })
+    }
+}
+
+impl FromIterator<TeamId> for TeamSet {
+    fn from_iter<I: IntoIterator<Item = TeamId>>(ids: I) -> TeamSet {
+        let mut set = TeamSet::new();
+        for id in ids {
+            set.insert(id);
+        }
+        set
+    }
+}
+
+impl fmt::Debug for TeamSet {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_set()
+            .entries(self.iter().map(|id| id.0))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::team_set::*;
+
+    #[test]
+    fn team_set() {
+        let mut set: TeamSet = [3, 0, 127].into_iter().map(TeamId).collect();
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(TeamId(127)) && !set.contains(TeamId(1)));
+        set.remove(TeamId(3));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![TeamId(0), TeamId(127)]);
+        assert_eq!(format!("{:?}", set), "{0, 127}");
+
+        let other: TeamSet = [0, 5].into_iter().map(TeamId).collect();
+        assert!(!set.is_disjoint(&other));
+        assert_eq!(set.union(&other).len(), 3);
+        assert_eq!(
+            set.intersection(&other).iter().collect::<Vec<_>>(),
+            vec![TeamId(0)]
+        );
+        assert_eq!(
+            set.difference(&other).iter().collect::<Vec<_>>(),
+            vec![TeamId(127)]
+        );
+        assert!(!set.contains(TeamId(200)));
+    }
+}
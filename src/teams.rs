@@ -1,9 +1,16 @@
+use chrono::NaiveDate;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::cmp;
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::fmt;
+use std::ops::{Index, IndexMut};
 use std::vec::Vec;
 
+use crate::error::ScheduleError;
+use crate::team_set::TeamSet;
+
 pub type Matrix = Vec<Vec<i32>>;
 
 pub fn increment_matrix(matrix: &mut Matrix, i0: usize, i1: usize) {
@@ -33,46 +40,269 @@ pub fn dump_matrix(matrix: Matrix) {
     }
 }
 
+// A team's position in State::teams, and a league's in State::leagues. Matrices are indexed by
+// the same positions, with .0.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TeamId(pub usize);
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LeagueId(pub usize);
+
+impl fmt::Display for TeamId {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}", self.0)
+    }
+}
+
+// A team in an input file: its name like "MAG 3", a league name for all of that league's teams, or
+// (as in older files) its index across all leagues.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum TeamRef {
+    Index(usize),
+    Name(String),
+}
+
+impl fmt::Display for TeamRef {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TeamRef::Index(ti) => write!(formatter, "{}", ti),
+            TeamRef::Name(name) => write!(formatter, "\"{}\"", name),
+        }
+    }
+}
+
+pub struct Venue {
+    pub name: String,
+    pub fields: usize,
+    // Fields available on specific dates, overriding `fields`. 0 closes the venue that day.
+    pub fields_on: BTreeMap<NaiveDate, usize>,
+}
+
+impl Venue {
+    pub fn fields_on(&self, date: NaiveDate) -> usize {
+        *self.fields_on.get(&date).unwrap_or(&self.fields)
+    }
+}
+
 pub struct League {
+    pub id: LeagueId,
     pub name: String,
     pub team_index_range: (usize, usize),
+    pub venues: Vec<Venue>,
+    // Times each pair of teams in the league plays each other.
+    pub meetings: usize,
 }
 
 impl League {
     fn num_teams(&self) -> usize {
         return self.team_index_range.1 - self.team_index_range.0;
     }
+
+    // How many games the league can host on a date, or None if it has no venues listed.
+    pub fn fields_on(&self, date: NaiveDate) -> Option<usize> {
+        if self.venues.is_empty() {
+            return None;
+        }
+        Some(self.venues.iter().map(|v| v.fields_on(date)).sum())
+    }
+}
+
+// Sum of league distances to every opponent, per team.
+pub fn get_travel_scores(state: &State, league_distance_matrix: &Matrix) -> Vec<i32> {
+    state
+        .teams
+        .iter()
+        .map(|team| {
+            team.teams_against
+                .iter()
+                .map(|id| {
+                    get_matrix_val(
+                        league_distance_matrix,
+                        team.league.0,
+                        state.teams[*id].league.0,
+                    )
+                })
+                .sum()
+        })
+        .collect()
+}
+
+// (team, games short) for every team short of its target.
+pub fn get_shortfalls(state: &State) -> Vec<(TeamId, i32)> {
+    state
+        .teams
+        .iter()
+        .filter(|t| t.num_games < t.target_games)
+        .map(|t| (t.id, t.target_games - t.num_games))
+        .collect()
+}
+
+// Errors with every team short of its target, if any.
+pub fn check_shortfalls(state: &State) -> Result<(), ScheduleError> {
+    let shortfalls = get_shortfalls(state);
+    if shortfalls.is_empty() {
+        return Ok(());
+    }
+    Err(ScheduleError::GamesShort(
+        shortfalls
+            .into_iter()
+            .map(|(id, games)| (state.team_name(id).to_string(), games))
+            .collect(),
+    ))
+}
+
+// Games teams are short of their targets, summed over all teams.
+pub fn get_games_short(state: &State) -> i32 {
+    state
+        .teams
+        .iter()
+        .map(|t| (t.target_games - t.num_games).max(0))
+        .sum()
 }
 
 pub fn dump_travel_scores(state: &State, league_distance_matrix: &Matrix) {
     let mut total_travel_score = 0;
 
     println!("Travel scores:");
-    for (ti0, team) in state.teams.iter().enumerate() {
-        let li0 = state.teams[ti0].league_index;
-
-        let mut team_travel_score = 0;
-        for ti1 in team.teams_against.iter() {
-            let li1 = state.teams[*ti1].league_index;
-            team_travel_score += get_matrix_val(league_distance_matrix, li0, li1);
-        }
-        let league_name = &state.leagues[li0].name;
-        let team_num = ti0 - state.leagues[li0].team_index_range.0;
-        println!(" - {}{}: {}", league_name, team_num, team_travel_score);
+    for (team, team_travel_score) in state
+        .teams
+        .iter()
+        .zip(get_travel_scores(state, league_distance_matrix))
+    {
+        println!(" - {}: {}", team.name, team_travel_score);
         total_travel_score += team_travel_score;
     }
     println!("Total travel score: {}", total_travel_score);
 }
 
 pub struct Team {
-    pub league_index: usize,
+    pub id: TeamId,
+    // Name as published, e.g. "MAG 3". Team numbers start at 1 within each league.
+    pub name: String,
+    pub league: LeagueId,
     pub num_games: i32,
-    pub teams_against: Vec<usize>,
+    // Games the team should end up with.
+    pub target_games: i32,
+    pub teams_against: Vec<TeamId>,
+}
+
+impl Team {
+    fn has_room(&self) -> bool {
+        self.num_games < self.target_games
+    }
+}
+
+// Days without a game a team needs between games. Teams in a group share coaches or pitchers,
+// so a game by any of them counts for all of them, with the group's (usually longer) rest.
+#[derive(Clone, Default)]
+pub struct Rest {
+    pub min_days: usize,
+    pub groups: Vec<RestGroup>,
+}
+
+impl Rest {
+    // Whether a team has rest rules, which let it sit out days it's available for and so have
+    // more days than games.
+    pub fn can_rest(&self, id: TeamId) -> bool {
+        self.min_days > 0 || self.groups.iter().any(|g| g.teams.contains(&id))
+    }
+}
+
+#[derive(Clone)]
+pub struct RestGroup {
+    pub teams: Vec<TeamId>,
+    pub min_days: usize,
 }
 
 pub struct State {
     pub leagues: Vec<League>,
     pub teams: Vec<Team>,
+    pub rest: Rest,
+    // Most weekend games a team can have more than another, or None for no limit.
+    pub weekend_tolerance: Option<usize>,
+    // Fewest days apart two meetings of the same pair of teams can be.
+    pub rematch_min_days: usize,
+}
+
+impl Index<TeamId> for Vec<Team> {
+    type Output = Team;
+
+    fn index(&self, id: TeamId) -> &Team {
+        &self[id.0]
+    }
+}
+
+impl IndexMut<TeamId> for Vec<Team> {
+    fn index_mut(&mut self, id: TeamId) -> &mut Team {
+        &mut self[id.0]
+    }
+}
+
+impl Index<LeagueId> for Vec<League> {
+    type Output = League;
+
+    fn index(&self, id: LeagueId) -> &League {
+        &self[id.0]
+    }
+}
+
+impl State {
+    pub fn team_name(&self, id: TeamId) -> &str {
+        &self.teams[id].name
+    }
+
+    pub fn team_id(&self, name: &str) -> Option<TeamId> {
+        self.teams.iter().find(|t| t.name == name).map(|t| t.id)
+    }
+
+    // Teams a reference in an input file stands for, or None if there's no such team or league.
+    pub fn resolve(&self, team: &TeamRef) -> Option<Vec<TeamId>> {
+        match team {
+            TeamRef::Index(ti) => (*ti < self.teams.len()).then(|| vec![TeamId(*ti)]),
+            TeamRef::Name(name) => match self.team_id(name) {
+                Some(id) => Some(vec![id]),
+                None => self.leagues.iter().find(|l| l.name == *name).map(|l| {
+                    let (first, last) = l.team_index_range;
+                    (first..last).map(TeamId).collect()
+                }),
+            },
+        }
+    }
+
+    // Every team any of the references stand for, or the first reference that's unknown.
+    pub fn resolve_all<'a>(
+        &self,
+        teams: impl IntoIterator<Item = &'a TeamRef>,
+    ) -> Result<Vec<TeamId>, &'a TeamRef> {
+        let mut ids = Vec::new();
+        for team in teams {
+            ids.extend(self.resolve(team).ok_or(team)?);
+        }
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    // Every team but the ones referenced, or the first reference that's unknown.
+    pub fn teams_except<'a>(
+        &self,
+        teams: impl IntoIterator<Item = &'a TeamRef>,
+    ) -> Result<TeamSet, &'a TeamRef> {
+        let not_these_teams = self.resolve_all(teams)?;
+        Ok(self
+            .teams
+            .iter()
+            .map(|t| t.id)
+            .filter(|id| !not_these_teams.contains(id))
+            .collect())
+    }
+
+    pub fn set_target_games(&mut self, target_games: i32) {
+        for team in self.teams.iter_mut() {
+            team.target_games = target_games;
+        }
+    }
 }
 
 impl fmt::Debug for State {
@@ -88,14 +318,8 @@ impl fmt::Debug for State {
                     ti,
                     team.num_games
                 )?;
-                for ti in team.teams_against.iter() {
-                    let league_index = self.teams[*ti].league_index;
-                    write!(
-                        formatter,
-                        "{}{},",
-                        self.leagues[league_index].name,
-                        *ti - self.leagues[league_index].team_index_range.0 + 1
-                    )?;
+                for id in team.teams_against.iter() {
+                    write!(formatter, "{},", self.teams[*id].name.replace(' ', ""))?;
                 }
                 write!(formatter, "\n")?;
             }
@@ -106,27 +330,63 @@ impl fmt::Debug for State {
 
 pub fn add_game(teams: &mut Vec<Team>, teams_matrix: &mut Matrix, ti0: usize, ti1: usize) {
     teams[ti0].num_games += 1;
-    teams[ti0].teams_against.push(ti1);
+    teams[ti0].teams_against.push(TeamId(ti1));
 
     teams[ti1].num_games += 1;
-    teams[ti1].teams_against.push(ti0);
+    teams[ti1].teams_against.push(TeamId(ti0));
 
     increment_matrix(teams_matrix, ti0, ti1);
 }
 
-pub fn assign_in_league_games(state: &mut State, teams_matrix: &mut Matrix, max_games: i32) {
-    for league in state.leagues.iter() {
-        assert!(
-            (league.num_teams() - 1) * 2 <= max_games as usize,
-            "Too many games in league for inter-league."
+pub fn remove_game(teams: &mut [Team], teams_matrix: &mut Matrix, ti0: usize, ti1: usize) {
+    for (ti, other) in [(ti0, ti1), (ti1, ti0)] {
+        teams[ti].num_games -= 1;
+        if let Some(i) = teams[ti].teams_against.iter().position(|t| t.0 == other) {
+            teams[ti].teams_against.remove(i);
+        }
+    }
+    if ti0 <= ti1 {
+        teams_matrix[ti0][ti1] -= 1;
+    } else {
+        teams_matrix[ti1][ti0] -= 1;
+    }
+}
+
+// Rounds of a round robin by the circle method: every team plays every other team once over the
+// rounds, and at most once per round. With an odd number of teams one team has a bye each round.
+fn get_round_robin_rounds(first: usize, last: usize) -> Vec<Vec<(usize, usize)>> {
+    let mut teams: Vec<Option<usize>> = (first..last).map(Some).collect();
+    if teams.len() % 2 == 1 {
+        teams.push(None);
+    }
+    let n = teams.len();
+    let mut rounds = Vec::new();
+    for _ in 1..n {
+        rounds.push(
+            (0..n / 2)
+                .filter_map(|i| match (teams[i], teams[n - 1 - i]) {
+                    (Some(ti0), Some(ti1)) => Some((ti0.min(ti1), ti0.max(ti1))),
+                    _ => None,
+                })
+                .collect(),
         );
-        for combo in (league.team_index_range.0..league.team_index_range.1)
-            .into_iter()
-            .combinations(2)
-        {
-            // Teams play other in-league teams twice.
-            add_game(&mut state.teams, teams_matrix, combo[0], combo[1]);
-            add_game(&mut state.teams, teams_matrix, combo[0], combo[1]);
+        teams[1..].rotate_right(1);
+    }
+    rounds
+}
+
+// Teams play other in-league teams `meetings` times, one round robin after another. Games that
+// would take a team past its target are skipped, leaving a partial round robin.
+pub fn assign_in_league_games(state: &mut State, teams_matrix: &mut Matrix) {
+    for li in 0..state.leagues.len() {
+        let (first, last) = state.leagues[li].team_index_range;
+        let rounds = get_round_robin_rounds(first, last);
+        for _ in 0..state.leagues[li].meetings {
+            for (ti0, ti1) in rounds.iter().flatten() {
+                if state.teams[*ti0].has_room() && state.teams[*ti1].has_room() {
+                    add_game(&mut state.teams, teams_matrix, *ti0, *ti1);
+                }
+            }
         }
     }
 }
@@ -136,7 +396,6 @@ pub fn assign_minimum_interleague_games(
     state: &mut State,
     teams_matrix: &mut Matrix,
     league_distance_matrix: &Matrix,
-    max_games: i32,
 ) {
     let mut leagues_next_index: Vec<usize> =
         state.leagues.iter().map(|l| l.team_index_range.0).collect();
@@ -165,8 +424,8 @@ pub fn assign_minimum_interleague_games(
             let ti0 = leagues_next_index[comb.li0];
             let ti1 = leagues_next_index[comb.li1];
 
-            if state.teams[ti0].num_games == max_games || state.teams[ti1].num_games == max_games {
-                // One of the leagues is full. Stop.
+            if !state.teams[ti0].has_room() || !state.teams[ti1].has_room() {
+                // One of the teams is full. Stop.
                 break;
             }
             add_game(&mut state.teams, teams_matrix, ti0, ti1);
@@ -235,17 +494,16 @@ pub fn assign_remaining_games(
     state: &mut State,
     teams_matrix: &mut Matrix,
     league_distance_matrix: &Matrix,
-    max_games: i32,
 ) {
     loop {
         let mut gco: Option<GameCompare> = None;
         for (ti0, team0) in state.teams.iter().enumerate() {
-            if team0.num_games == max_games {
+            if !team0.has_room() {
                 continue;
             }
             for ti1 in ti0 + 1..state.teams.len() {
                 let team1 = &state.teams[ti1];
-                if team1.num_games == max_games {
+                if !team1.has_room() {
                     continue;
                 }
                 let gco_candidate = Some(GameCompare {
@@ -257,8 +515,8 @@ pub fn assign_remaining_games(
                     num_league_games_against: 0,
                     distance: get_matrix_val(
                         league_distance_matrix,
-                        team0.league_index,
-                        team1.league_index,
+                        team0.league.0,
+                        team1.league.0,
                     ),
                 });
 
@@ -275,25 +533,211 @@ pub fn assign_remaining_games(
     }
 }
 
+// assign_remaining_games stops when at most one team has room left, since a team can't play
+// itself. A team two or more games short takes over an interleague game between two other teams
+// instead: x - y becomes team - x and team - y, picking the game that adds the least travel. The
+// new games can't take a pair past its league's meetings, or an interleague pair past the most
+// times any interleague pair already meets.
+pub fn repair_games_short(
+    state: &mut State,
+    teams_matrix: &mut Matrix,
+    league_distance_matrix: &Matrix,
+) {
+    let distance = |state: &State, ti0: usize, ti1: usize| {
+        get_matrix_val(
+            league_distance_matrix,
+            state.teams[ti0].league.0,
+            state.teams[ti1].league.0,
+        )
+    };
+    let interleague_meetings = (0..state.teams.len())
+        .flat_map(|ti0| (ti0 + 1..state.teams.len()).map(move |ti1| (ti0, ti1)))
+        .filter(|(ti0, ti1)| state.teams[*ti0].league != state.teams[*ti1].league)
+        .map(|(ti0, ti1)| teams_matrix[ti0][ti1])
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let can_meet_again = |state: &State, teams_matrix: &Matrix, ti0: usize, ti1: usize| {
+        let league = state.teams[ti0].league;
+        let max_meetings = if league == state.teams[ti1].league {
+            state.leagues[league].meetings as i32
+        } else {
+            interleague_meetings
+        };
+        get_matrix_val(teams_matrix, ti0, ti1) < max_meetings
+    };
+    while let Some(ti) = (0..state.teams.len())
+        .find(|ti| state.teams[*ti].target_games - state.teams[*ti].num_games >= 2)
+    {
+        let mut best: Option<((i32, i32), usize, usize)> = None;
+        for x in 0..state.teams.len() {
+            for y in x + 1..state.teams.len() {
+                if x == ti
+                    || y == ti
+                    || teams_matrix[x][y] == 0
+                    || state.teams[x].league == state.teams[y].league
+                    || !can_meet_again(state, teams_matrix, ti, x)
+                    || !can_meet_again(state, teams_matrix, ti, y)
+                {
+                    continue;
+                }
+                let cost = (
+                    distance(state, ti, x) + distance(state, ti, y) - distance(state, x, y),
+                    get_matrix_val(teams_matrix, ti, x) + get_matrix_val(teams_matrix, ti, y),
+                );
+                if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, x, y));
+                }
+            }
+        }
+        let Some((_, x, y)) = best else {
+            break;
+        };
+        remove_game(&mut state.teams, teams_matrix, x, y);
+        add_game(&mut state.teams, teams_matrix, ti, x);
+        add_game(&mut state.teams, teams_matrix, ti, y);
+    }
+}
+
+// Assigns games until every team reaches its target_games, or errors with the teams that can't.
 pub fn get_teams_to_play_against(
     state: &mut State,
     league_distance_matrix: &Matrix,
-    max_games: i32,
-) -> Matrix {
+) -> Result<Matrix, ScheduleError> {
     let mut teams_matrix = create_matrix_with_dimension(state.teams.len());
 
     // Assign games.
-    assign_in_league_games(state, &mut teams_matrix, max_games);
-    assign_minimum_interleague_games(state, &mut teams_matrix, &league_distance_matrix, max_games);
-    assign_remaining_games(state, &mut teams_matrix, &league_distance_matrix, max_games);
+    assign_in_league_games(state, &mut teams_matrix);
+    assign_minimum_interleague_games(state, &mut teams_matrix, league_distance_matrix);
+    assign_remaining_games(state, &mut teams_matrix, league_distance_matrix);
+    repair_games_short(state, &mut teams_matrix, league_distance_matrix);
 
-    return teams_matrix;
+    check_shortfalls(state)?;
+    Ok(teams_matrix)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::sample_data::get_state_from_league_info;
     use crate::teams::*;
 
+    #[test]
+    fn partial_round_robin() {
+        let mut state = get_state_from_league_info(&[("MAG".to_string(), 5)], 7);
+        let mut matrix = create_matrix_with_dimension(5);
+        assign_in_league_games(&mut state, &mut matrix);
+        // One full round robin (4 games each), then as much of another as fits.
+        assert!(state
+            .teams
+            .iter()
+            .all(|t| t.num_games >= 6 && t.num_games <= 7));
+        assert!((0..5).all(|ti0| (ti0 + 1..5).all(|ti1| matrix[ti0][ti1] >= 1)));
+
+        let mut state = get_state_from_league_info(&[("MAG".to_string(), 4)], 9);
+        state.leagues[0].meetings = 3;
+        let mut matrix = create_matrix_with_dimension(4);
+        assign_in_league_games(&mut state, &mut matrix);
+        assert!((0..4).all(|ti0| (ti0 + 1..4).all(|ti1| matrix[ti0][ti1] == 3)));
+    }
+
+    #[test]
+    fn target_games() {
+        let mut state =
+            get_state_from_league_info(&[("SL".to_string(), 2), ("NE".to_string(), 4)], 8);
+        state.teams[1].target_games = 4;
+        let mut league_distance_matrix = create_matrix_with_dimension(2);
+        league_distance_matrix[0][1] = 1;
+        get_teams_to_play_against(&mut state, &league_distance_matrix).unwrap();
+        assert_eq!(state.teams[1].num_games, 4);
+        assert!(state.teams.iter().all(|t| t.num_games <= t.target_games));
+    }
+
+    #[test]
+    fn shortfalls() {
+        let league_info = vec![
+            ("A".to_string(), 1),
+            ("B".to_string(), 1),
+            ("C".to_string(), 1),
+            ("D".to_string(), 1),
+        ];
+        let league_distance_matrix = create_matrix_with_dimension(4);
+
+        // B and C are full after playing each other, so A takes over their game.
+        let mut state = get_state_from_league_info(&league_info[..3], 1);
+        state.teams[0].target_games = 2;
+        let mut three_leagues = create_matrix_with_dimension(3);
+        three_leagues[0][1] = 3;
+        three_leagues[0][2] = 3;
+        three_leagues[1][2] = 1;
+        let matrix = get_teams_to_play_against(&mut state, &three_leagues).unwrap();
+        assert!(state.teams.iter().all(|t| t.num_games == t.target_games));
+        assert_eq!(matrix[0][1..], [1, 1]);
+        assert_eq!(matrix[1][2], 0);
+
+        // Everyone else is full after playing each other once, and taking over one of their
+        // games would have A meet a team twice when no other teams do.
+        let mut state = get_state_from_league_info(&league_info, 3);
+        state.teams[0].target_games = 5;
+        assert!(matches!(
+            get_teams_to_play_against(&mut state, &league_distance_matrix),
+            Err(ScheduleError::GamesShort(teams)) if teams == vec![("A 1".to_string(), 2)]
+        ));
+
+        // An odd number of team games can't work out.
+        let mut state = get_state_from_league_info(&league_info, 3);
+        state.teams[0].target_games = 4;
+        assert!(matches!(
+            get_teams_to_play_against(&mut state, &league_distance_matrix),
+            Err(ScheduleError::GamesShort(teams)) if teams == vec![("A 1".to_string(), 1)]
+        ));
+    }
+
+    #[test]
+    fn repair_meetings() {
+        // The only game to take over is A 1 - B 2, which would have B 1 meet A 1 twice when
+        // interleague pairs only meet once.
+        let mut state =
+            get_state_from_league_info(&[("A".to_string(), 1), ("B".to_string(), 2)], 2);
+        state.leagues[1].meetings = 1;
+        state.teams[1].target_games = 3;
+        let mut matrix = create_matrix_with_dimension(3);
+        add_game(&mut state.teams, &mut matrix, 0, 1);
+        add_game(&mut state.teams, &mut matrix, 0, 2);
+        let league_distance_matrix = create_matrix_with_dimension(2);
+        repair_games_short(&mut state, &mut matrix, &league_distance_matrix);
+        assert_eq!(state.teams[1].num_games, 1);
+
+        // Here it's B 2 - A 1, which would have B 1 meet B 2 twice in a league that meets once.
+        let mut state =
+            get_state_from_league_info(&[("A".to_string(), 1), ("B".to_string(), 2)], 2);
+        state.leagues[1].meetings = 1;
+        state.teams[1].target_games = 3;
+        let mut matrix = create_matrix_with_dimension(3);
+        add_game(&mut state.teams, &mut matrix, 1, 2);
+        add_game(&mut state.teams, &mut matrix, 0, 2);
+        repair_games_short(&mut state, &mut matrix, &league_distance_matrix);
+        assert_eq!(state.teams[1].num_games, 1);
+        assert_eq!(matrix[1][2], 1);
+    }
+
+    #[test]
+    fn resolve() {
+        let state = get_state_from_league_info(&[("SL".to_string(), 2), ("NE".to_string(), 3)], 4);
+        assert_eq!(state.team_name(TeamId(3)), "NE 2");
+        assert_eq!(state.team_id("NE 2"), Some(TeamId(3)));
+        let refs = [
+            TeamRef::Name("NE".to_string()),
+            TeamRef::Name("SL 1".to_string()),
+            TeamRef::Index(2),
+        ];
+        assert_eq!(
+            state.resolve_all(refs.iter()),
+            Ok((0..5).filter(|ti| *ti != 1).map(TeamId).collect())
+        );
+        let unknown = [TeamRef::Name("NE 4".to_string())];
+        assert_eq!(state.resolve_all(unknown.iter()), Err(&unknown[0]));
+    }
+
     #[test]
     fn matrix_tests() {
         let mut exp = create_matrix_with_dimension(2);